@@ -1,9 +1,24 @@
-use crate::game::{Car, RaceState};
+use crate::catalog::{CarCatalog, CatalogError};
+use crate::game::{
+    AiDifficulty, Car, GHOST_SAMPLE_INTERVAL, Ghost, GhostSnapshot, PerfectRun, RaceMode,
+    RaceSettings, RaceState, ThrottleResponse, TrackConditions, TreeType,
+};
+use crate::replay::{self, InputEvent, PlayerInput, Replay};
+use std::collections::HashMap;
+
+// Physics runs at a fixed 1 kHz regardless of the render rate
+const PHYSICS_TIMESTEP: f64 = 0.001;
+// Cap on real time simulated per frame so a long hitch doesn't stall the loop
+const MAX_FRAME_TIME: f64 = 0.25;
+const DIAL_IN_STEP: f64 = 0.05;
+// Slowest bracket dial-in, past anything a street car runs, so the stagger stays sane
+const MAX_DIAL_IN: f64 = 30.0;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AppState {
     Menu,
     Racing,
+    Replay,
     Results,
 }
 
@@ -12,10 +27,30 @@ pub struct App {
     pub race_state: Option<RaceState>,
     pub player_car: Car,
     pub opponent_car: Car,
+    pub cars: Vec<Car>,
+    pub car_load_errors: Vec<CatalogError>,
     pub should_quit: bool,
     pub selected_car_index: usize,
     pub key_states: KeyStates,
     pub audio_muted: bool,
+    pub last_replay: Option<Replay>,
+    pub status_message: Option<String>,
+    pub racing_ghost: bool,
+    pub bracket_mode: bool,
+    pub player_dial_in: f64,
+    pub tree_type: TreeType,
+    pub throttle_response: ThrottleResponse,
+    pub ai_difficulty: AiDifficulty,
+    // Weather and track surface, drawn once per session
+    pub conditions: TrackConditions,
+    // What the AI and a perfect driver run in each car today, worked out the first time
+    // the car is selected since the conditions don't change
+    estimated_ets: HashMap<String, Option<f64>>,
+    perfect_runs: HashMap<String, PerfectRun>,
+    physics_accumulator: f64,
+    recorded_inputs: Vec<InputEvent>,
+    ghost_snapshots: Vec<GhostSnapshot>,
+    playback: Option<Playback>,
 }
 
 #[derive(Debug, Clone)]
@@ -23,47 +58,257 @@ pub struct KeyStates {
     pub throttle_pressed: bool,
     pub nitrous_pressed: bool,
     pub shift_pressed: bool,
+    pub launch_control_pressed: bool,
+    pub clutch_pressed: bool,
+}
+
+// Feeds a replay's recorded inputs back into the race at their recorded times
+struct Playback {
+    events: Vec<InputEvent>,
+    next_event: usize,
+    key_states: KeyStates,
+}
+
+impl Playback {
+    fn apply_due_events(&mut self, race: &mut RaceState) {
+        while let Some(event) = self.events.get(self.next_event) {
+            if event.time > race.sim_time() {
+                break;
+            }
+            match event.input {
+                PlayerInput::ThrottlePressed => self.key_states.throttle_pressed = true,
+                PlayerInput::ThrottleReleased => self.key_states.throttle_pressed = false,
+                PlayerInput::NitrousPressed => self.key_states.nitrous_pressed = true,
+                PlayerInput::NitrousReleased => self.key_states.nitrous_pressed = false,
+                PlayerInput::LaunchControlPressed => self.key_states.launch_control_pressed = true,
+                PlayerInput::LaunchControlReleased => {
+                    self.key_states.launch_control_pressed = false
+                }
+                PlayerInput::ClutchPressed => self.key_states.clutch_pressed = true,
+                PlayerInput::ClutchReleased => self.key_states.clutch_pressed = false,
+                PlayerInput::ShiftUp => race.player_shift_up(),
+                PlayerInput::ThrottleLimit(limit) => race.player_set_throttle_limit(limit),
+            }
+            self.next_event += 1;
+        }
+    }
+}
+
+impl KeyStates {
+    fn new() -> Self {
+        Self {
+            throttle_pressed: false,
+            nitrous_pressed: false,
+            shift_pressed: false,
+            launch_control_pressed: false,
+            clutch_pressed: false,
+        }
+    }
 }
 
 impl App {
     pub fn new() -> Self {
-        Self {
+        let catalog = CarCatalog::load();
+        let player_car = catalog.cars[0].clone();
+        // Different opponent car when the catalog has more than one
+        let opponent_car = catalog.cars.get(1).unwrap_or(&catalog.cars[0]).clone();
+        let conditions = TrackConditions::random(&mut rand::rng());
+
+        let mut app = Self {
             state: AppState::Menu,
             race_state: None,
-            player_car: Car::civic(),
-            opponent_car: Car::mustang(), // Different opponent car
+            player_car,
+            opponent_car,
+            cars: catalog.cars,
+            car_load_errors: catalog.errors,
             should_quit: false,
             selected_car_index: 0,
-            key_states: KeyStates {
-                throttle_pressed: false,
-                nitrous_pressed: false,
-                shift_pressed: false,
-            },
+            key_states: KeyStates::new(),
             audio_muted: false,
-        }
+            last_replay: None,
+            status_message: None,
+            racing_ghost: false,
+            bracket_mode: false,
+            player_dial_in: 0.0,
+            tree_type: TreeType::default(),
+            throttle_response: ThrottleResponse::Quick,
+            ai_difficulty: AiDifficulty::default(),
+            conditions,
+            estimated_ets: HashMap::new(),
+            perfect_runs: HashMap::new(),
+            physics_accumulator: 0.0,
+            recorded_inputs: Vec::new(),
+            ghost_snapshots: Vec::new(),
+            playback: None,
+        };
+        app.car_selected();
+        app
     }
 
+    pub fn start_quick_race(&mut self) {
+        self.racing_ghost = false;
+        self.start_race();
+    }
+
+    /// Race against the personal best ghost for the selected car
+    pub fn start_ghost_race(&mut self) {
+        self.racing_ghost = true;
+        self.start_race();
+    }
+
+    /// Start a new race in the current mode
     pub fn start_race(&mut self) {
+        let race = if self.racing_ghost {
+            match replay::load_ghost(&self.player_car.name) {
+                Ok(Some(ghost)) => {
+                    // A ghost dials in its own best ET
+                    let settings = self.race_settings(round_down_to_hundredths(ghost.et));
+                    RaceState::new_ghost_race(
+                        self.player_car.clone(),
+                        ghost,
+                        settings,
+                        rand::random(),
+                    )
+                }
+                Ok(None) => {
+                    self.status_message = Some(format!(
+                        "No personal best for the {} yet - finish a race to set one",
+                        self.player_car.name
+                    ));
+                    return;
+                }
+                Err(e) => {
+                    self.status_message = Some(format!("Could not load ghost: {}", e));
+                    return;
+                }
+            }
+        } else {
+            // The AI sets its own dial-in once it has planned its race
+            let settings = self.race_settings(self.player_dial_in);
+            let opponent_car = self.opponent_car.clone();
+            let perfect_shift_rpms = self.perfect_shift_rpms(&opponent_car, &settings);
+            RaceState::new(
+                self.player_car.clone(),
+                opponent_car,
+                settings,
+                rand::random(),
+                &perfect_shift_rpms,
+            )
+        };
+
         self.reset_all_key_states();
-        self.race_state = Some(RaceState::new(
-            self.player_car.clone(),
-            self.opponent_car.clone(),
-        ));
+        self.race_state = Some(race);
+        self.physics_accumulator = 0.0;
+        self.recorded_inputs.clear();
+        self.ghost_snapshots.clear();
+        self.playback = None;
+        self.status_message = None;
         self.state = AppState::Racing;
     }
 
+    pub fn start_replay(&mut self, replay: Replay) {
+        self.reset_all_key_states();
+        self.race_state = Some(match replay.ghost.clone() {
+            Some(ghost) => RaceState::new_ghost_race(
+                replay.player_car.clone(),
+                ghost,
+                replay.settings,
+                replay.seed,
+            ),
+            None => RaceState::new(
+                replay.player_car.clone(),
+                replay.opponent_car.clone(),
+                replay.settings,
+                replay.seed,
+                &self.perfect_shift_rpms(&replay.opponent_car, &replay.settings),
+            ),
+        });
+        self.physics_accumulator = 0.0;
+        self.playback = Some(Playback {
+            events: replay.events.clone(),
+            next_event: 0,
+            key_states: KeyStates::new(),
+        });
+        self.last_replay = Some(replay);
+        self.status_message = None;
+        self.state = AppState::Replay;
+    }
+
+    /// Re-watch the most recent race
+    pub fn watch_last_replay(&mut self) {
+        if let Some(replay) = self.last_replay.clone() {
+            self.start_replay(replay);
+        }
+    }
+
+    /// Watch the most recently saved replay file
+    pub fn load_latest_replay(&mut self) {
+        match Replay::load_latest() {
+            Ok(replay) => self.start_replay(replay),
+            Err(e) => self.status_message = Some(format!("Could not load replay: {}", e)),
+        }
+    }
+
+    pub fn stop_race(&mut self) {
+        self.reset_all_key_states();
+        self.playback = None;
+        self.state = AppState::Menu;
+    }
+
     pub fn update(&mut self, delta_time: f64) {
+        if !matches!(self.state, AppState::Racing | AppState::Replay) {
+            return;
+        }
+
+        self.physics_accumulator += delta_time.min(MAX_FRAME_TIME);
+        while self.physics_accumulator >= PHYSICS_TIMESTEP {
+            self.physics_accumulator -= PHYSICS_TIMESTEP;
+            self.step(PHYSICS_TIMESTEP);
+            if self.playback.is_none() {
+                self.record_ghost_snapshot();
+            }
+
+            if self.state == AppState::Results {
+                self.physics_accumulator = 0.0;
+                self.finish_race();
+                break;
+            }
+        }
+    }
+
+    fn step(&mut self, delta_time: f64) {
         // Handle continuous key states
         if let Some(race) = &mut self.race_state {
+            let key_states = match &mut self.playback {
+                Some(playback) => {
+                    playback.apply_due_events(race);
+                    &playback.key_states
+                }
+                None => &self.key_states,
+            };
+
+            // Launch control and clutch first, so letting go of them with the throttle
+            // held launches this step
+            if key_states.launch_control_pressed {
+                race.player_engage_launch_control();
+            } else {
+                race.player_release_launch_control();
+            }
+            if key_states.clutch_pressed {
+                race.player_press_clutch();
+            } else {
+                race.player_release_clutch();
+            }
+
             // Update throttle based on key state
-            if self.key_states.throttle_pressed {
+            if key_states.throttle_pressed {
                 race.player_throttle();
             } else {
                 race.player_release_throttle();
             }
 
             // Update nitrous based on key state
-            if self.key_states.nitrous_pressed {
+            if key_states.nitrous_pressed {
                 race.player_activate_nos();
             } else {
                 race.player_deactivate_nos();
@@ -77,17 +322,165 @@ impl App {
         }
     }
 
+    fn finish_race(&mut self) {
+        // Replays are already on disk, only live races get saved
+        if self.playback.take().is_some() {
+            return;
+        }
+        let Some(race) = &self.race_state else {
+            return;
+        };
+
+        let replay = Replay::new(
+            race.player_car.clone(),
+            race.opponent_car.clone(),
+            race.seed,
+            race.settings,
+            std::mem::take(&mut self.recorded_inputs),
+            race.ghost.clone(),
+        );
+        let mut message = match replay.save() {
+            Ok(path) => format!("Replay saved to {}", path.display()),
+            Err(e) => format!("Could not save replay: {}", e),
+        };
+
+        if let Some(pb_message) = self.save_personal_best() {
+            message = format!("{} | {}", pb_message, message);
+        }
+
+        self.status_message = Some(message);
+        self.last_replay = Some(replay);
+    }
+
+    // Sample the player's lane for a future ghost at a fixed interval after green
+    fn record_ghost_snapshot(&mut self) {
+        let Some(race) = &self.race_state else {
+            return;
+        };
+        let Some(time) = race.time_since_green() else {
+            return;
+        };
+
+        let due = self
+            .ghost_snapshots
+            .last()
+            .is_none_or(|last| time - last.time >= GHOST_SAMPLE_INTERVAL);
+        // Always capture the step the player crosses the line so the ghost finishes too
+        let just_finished = race.player.finish_time.is_some()
+            && self
+                .ghost_snapshots
+                .last()
+                .is_some_and(|last| last.state.finish_time.is_none());
+
+        if due || just_finished {
+            self.ghost_snapshots.push(GhostSnapshot {
+                time,
+                state: race.player.clone(),
+            });
+        }
+    }
+
+    /// Save this run as the car's ghost if it beat the previous best ET
+    fn save_personal_best(&mut self) -> Option<String> {
+        let race = self.race_state.as_ref()?;
+        // A red-lit run still gets an ET, but a ghost can't leave before green
+        if race.player.red_light {
+            return None;
+        }
+        let et = race.player.finish_time?;
+        let launch_delay = race.player_launch_delay()?;
+
+        if let Ok(Some(best)) = replay::load_ghost(&race.player_car.name)
+            && best.et <= et
+        {
+            return None;
+        }
+
+        let ghost = Ghost {
+            car: race.player_car.clone(),
+            et,
+            launch_delay,
+            snapshots: std::mem::take(&mut self.ghost_snapshots),
+        };
+        Some(match replay::save_ghost(&ghost) {
+            Ok(_) => format!("New personal best: {:.3}s", et),
+            Err(e) => format!("Could not save personal best: {}", e),
+        })
+    }
+
+    fn record_input(&mut self, input: PlayerInput) {
+        if self.state != AppState::Racing {
+            return;
+        }
+        if let Some(race) = &self.race_state {
+            self.recorded_inputs.push(InputEvent {
+                time: race.sim_time(),
+                input,
+            });
+        }
+    }
+
     pub fn set_throttle_pressed(&mut self, pressed: bool) {
+        // Key repeat sends extra presses, only changes are recorded
+        if self.key_states.throttle_pressed != pressed {
+            self.record_input(if pressed {
+                PlayerInput::ThrottlePressed
+            } else {
+                PlayerInput::ThrottleReleased
+            });
+        }
         self.key_states.throttle_pressed = pressed;
     }
 
     pub fn set_nitrous_pressed(&mut self, pressed: bool) {
+        if self.key_states.nitrous_pressed != pressed {
+            self.record_input(if pressed {
+                PlayerInput::NitrousPressed
+            } else {
+                PlayerInput::NitrousReleased
+            });
+        }
         self.key_states.nitrous_pressed = pressed;
     }
 
+    pub fn set_launch_control_pressed(&mut self, pressed: bool) {
+        if self.key_states.launch_control_pressed != pressed {
+            self.record_input(if pressed {
+                PlayerInput::LaunchControlPressed
+            } else {
+                PlayerInput::LaunchControlReleased
+            });
+        }
+        self.key_states.launch_control_pressed = pressed;
+    }
+
+    pub fn set_clutch_pressed(&mut self, pressed: bool) {
+        if self.key_states.clutch_pressed != pressed {
+            self.record_input(if pressed {
+                PlayerInput::ClutchPressed
+            } else {
+                PlayerInput::ClutchReleased
+            });
+        }
+        self.key_states.clutch_pressed = pressed;
+    }
+
+    /// Number key throttle steps: 1-9 open the pedal 10-90% when held, 0 is full throttle
+    pub fn set_throttle_step(&mut self, step: u32) {
+        let limit = if step == 0 { 1.0 } else { step as f64 / 10.0 };
+        let Some(race) = &mut self.race_state else {
+            return;
+        };
+        if race.player_throttle_limit() != limit {
+            race.player_set_throttle_limit(limit);
+            self.record_input(PlayerInput::ThrottleLimit(limit));
+        }
+    }
+
     pub fn shift_up(&mut self) {
         if !self.key_states.shift_pressed {
             self.key_states.shift_pressed = true;
+            self.record_input(PlayerInput::ShiftUp);
             if let Some(race) = &mut self.race_state {
                 race.player_shift_up();
             }
@@ -98,30 +491,138 @@ impl App {
         self.key_states.shift_pressed = false;
     }
 
-    pub fn get_available_cars() -> Vec<Car> {
-        vec![Car::civic(), Car::mustang(), Car::gtr()]
+    fn race_settings(&self, opponent_dial: f64) -> RaceSettings {
+        let mode = if self.bracket_mode {
+            RaceMode::Bracket {
+                player_dial: self.player_dial_in,
+                opponent_dial,
+            }
+        } else {
+            RaceMode::HeadsUp
+        };
+
+        RaceSettings {
+            mode,
+            tree: self.tree_type,
+            throttle: self.throttle_response,
+            conditions: self.conditions,
+            difficulty: self.ai_difficulty,
+        }
+    }
+
+    /// Personal best ET if there is one, otherwise what the AI would run in the selected
+    /// car today
+    fn suggested_dial_in(&mut self) -> f64 {
+        if let Ok(Some(ghost)) = replay::load_ghost(&self.player_car.name) {
+            return round_down_to_hundredths(ghost.et);
+        }
+        let (car, conditions) = (&self.player_car, &self.conditions);
+        self.estimated_ets
+            .entry(car.name.clone())
+            .or_insert_with(|| RaceState::estimate_et(car, conditions))
+            .map(round_down_to_hundredths)
+            .unwrap_or(15.0)
+    }
+
+    /// How a perfect driver runs the selected car today
+    pub fn perfect_run(&self) -> Option<&PerfectRun> {
+        self.perfect_runs.get(&self.player_car.name)
+    }
+
+    // Where a perfect AI changes up in `car`, for races that have one. Today's runs are
+    // worked out once per car; a replay from another day is worked out afresh.
+    fn perfect_shift_rpms(&mut self, car: &Car, settings: &RaceSettings) -> Vec<u32> {
+        if settings.difficulty != AiDifficulty::Perfect {
+            return Vec::new();
+        }
+        if settings.conditions != self.conditions {
+            return RaceState::perfect_run(car, &settings.conditions).shift_rpms;
+        }
+        self.todays_perfect_run(car).shift_rpms.clone()
+    }
+
+    // How a perfect driver runs `car` today, worked out the first time it's needed
+    fn todays_perfect_run(&mut self, car: &Car) -> &PerfectRun {
+        let conditions = &self.conditions;
+        self.perfect_runs
+            .entry(car.name.clone())
+            .or_insert_with(|| RaceState::perfect_run(car, conditions))
+    }
+
+    /// The perfect driver's ET to compare `race` against, if it was run in the selected
+    /// car in today's conditions
+    pub fn race_benchmark_et(&self, race: &RaceState) -> Option<f64> {
+        self.perfect_run().and_then(|run| run.et).filter(|_| {
+            race.player_car.name == self.player_car.name
+                && race.settings.conditions == self.conditions
+        })
+    }
+
+    pub fn toggle_bracket_mode(&mut self) {
+        self.bracket_mode = !self.bracket_mode;
+    }
+
+    pub fn toggle_tree_type(&mut self) {
+        self.tree_type = match self.tree_type {
+            TreeType::Sportsman => TreeType::Pro,
+            TreeType::Pro => TreeType::Sportsman,
+        };
+    }
+
+    pub fn cycle_throttle_response(&mut self) {
+        self.throttle_response = match self.throttle_response {
+            ThrottleResponse::Instant => ThrottleResponse::Quick,
+            ThrottleResponse::Quick => ThrottleResponse::Progressive,
+            ThrottleResponse::Progressive => ThrottleResponse::Instant,
+        };
+    }
+
+    pub fn cycle_ai_difficulty(&mut self) {
+        self.ai_difficulty = match self.ai_difficulty {
+            AiDifficulty::Easy => AiDifficulty::Medium,
+            AiDifficulty::Medium => AiDifficulty::Hard,
+            AiDifficulty::Hard => AiDifficulty::Pro,
+            AiDifficulty::Pro => AiDifficulty::Perfect,
+            AiDifficulty::Perfect => AiDifficulty::Easy,
+        };
+    }
+
+    pub fn increase_dial_in(&mut self) {
+        self.player_dial_in = (self.player_dial_in + DIAL_IN_STEP).min(MAX_DIAL_IN);
+    }
+
+    pub fn decrease_dial_in(&mut self) {
+        self.player_dial_in = (self.player_dial_in - DIAL_IN_STEP).max(DIAL_IN_STEP);
     }
 
     pub fn select_next_car(&mut self) {
-        let cars = Self::get_available_cars();
-        self.selected_car_index = (self.selected_car_index + 1) % cars.len();
-        self.player_car = cars[self.selected_car_index].clone();
+        self.selected_car_index = (self.selected_car_index + 1) % self.cars.len();
+        self.car_selected();
     }
 
     pub fn select_previous_car(&mut self) {
-        let cars = Self::get_available_cars();
         self.selected_car_index = if self.selected_car_index == 0 {
-            cars.len() - 1
+            self.cars.len() - 1
         } else {
             self.selected_car_index - 1
         };
-        self.player_car = cars[self.selected_car_index].clone();
+        self.car_selected();
+    }
+
+    // Switch to the car at the selected index, with its dial-in and benchmark
+    fn car_selected(&mut self) {
+        self.player_car = self.cars[self.selected_car_index].clone();
+        self.player_dial_in = self.suggested_dial_in();
+        let car = self.player_car.clone();
+        self.todays_perfect_run(&car);
     }
 
     pub fn reset_all_key_states(&mut self) {
         self.key_states.throttle_pressed = false;
         self.key_states.nitrous_pressed = false;
         self.key_states.shift_pressed = false;
+        self.key_states.launch_control_pressed = false;
+        self.key_states.clutch_pressed = false;
     }
 
     pub fn toggle_mute(&mut self, audio_engine: Option<&crate::audio::AudioEngine>) {
@@ -133,3 +634,64 @@ impl App {
         }
     }
 }
+
+fn round_down_to_hundredths(seconds: f64) -> f64 {
+    (seconds * 100.0).floor() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::LightState;
+
+    const FRAME_TIME: f64 = 1.0 / 60.0;
+
+    #[test]
+    fn saved_replay_reproduces_the_race() {
+        let mut app = App::new();
+        app.start_quick_race();
+
+        // Floor it on the green, change up every second and pulse the nitrous, so plenty
+        // of inputs land at awkward simulation times
+        let mut frames = 0;
+        while app.state == AppState::Racing && frames < 60 * 60 {
+            let green = app.race_state.as_ref().is_some_and(|race| {
+                matches!(
+                    race.christmas_tree.state,
+                    LightState::Green | LightState::Racing
+                )
+            });
+            app.set_throttle_pressed(green);
+            if green && frames % 60 == 0 {
+                app.shift_up();
+            }
+            app.set_nitrous_pressed(green && frames % 20 < 7);
+            app.update(FRAME_TIME);
+            frames += 1;
+        }
+        assert_eq!(app.state, AppState::Results);
+        let live = app.race_state.take().unwrap();
+        let recorded = app.last_replay.clone().unwrap();
+
+        app.load_latest_replay();
+        assert_eq!(app.state, AppState::Replay);
+        while app.state == AppState::Replay {
+            app.update(FRAME_TIME);
+        }
+        let replayed = app.race_state.take().unwrap();
+
+        let loaded = app.last_replay.as_ref().unwrap();
+        assert_eq!(loaded.events.len(), recorded.events.len());
+        for (loaded, recorded) in loaded.events.iter().zip(&recorded.events) {
+            assert_eq!(loaded.time.to_bits(), recorded.time.to_bits());
+        }
+
+        assert!(live.player.finish_time.is_some());
+        assert_eq!(replayed.player.reaction_time, live.player.reaction_time);
+        assert_eq!(replayed.player.finish_time, live.player.finish_time);
+        assert_eq!(replayed.player.top_speed, live.player.top_speed);
+        assert_eq!(replayed.winner, live.winner);
+
+        let _ = std::fs::remove_dir_all(crate::catalog::config_dir().unwrap());
+    }
+}
@@ -14,6 +14,7 @@ struct AudioState {
     beep_timer: f32,
     beep_frequency: f32,
     beep_active: bool,
+    limiter_active: bool,
 }
 
 impl AudioEngine {
@@ -31,6 +32,7 @@ impl AudioEngine {
             beep_timer: 0.0,
             beep_frequency: 0.0,
             beep_active: false,
+            limiter_active: false,
         }));
 
         let stream = Self::build_stream(&device, &config.into(), state.clone())?;
@@ -69,7 +71,16 @@ impl AudioEngine {
                         // Add some noise for realism
                         let noise = (sample_clock % 100.0) / 100.0 - 0.5;
 
-                        (fundamental + harmonic2 + harmonic3 + noise * 0.1) * state.engine_amplitude
+                        // The two-step cuts spark in bursts, giving the engine a popping stutter
+                        let limiter_gate = if state.limiter_active && (time * 14.0).fract() >= 0.5 {
+                            0.3
+                        } else {
+                            1.0
+                        };
+
+                        (fundamental + harmonic2 + harmonic3 + noise * 0.1)
+                            * state.engine_amplitude
+                            * limiter_gate
                     } else {
                         0.0
                     };
@@ -113,8 +124,10 @@ impl AudioEngine {
     }
 
     /// Update engine sound based on RPM and throttle
-    pub fn update_engine(&self, rpm: u32, throttle: f32, redline: u32) {
+    pub fn update_engine(&self, rpm: u32, throttle: f32, redline: u32, on_limiter: bool) {
         if let Ok(mut state) = self.state.lock() {
+            state.limiter_active = on_limiter;
+
             // Map RPM to frequency (typical engine sounds are in the 50-400 Hz range)
             // Lower RPMs = lower frequency, higher RPMs = higher frequency
             let rpm_ratio = rpm as f32 / redline as f32;
@@ -174,6 +187,7 @@ impl AudioEngine {
             state.beep_active = false;
             state.beep_timer = 0.0;
             state.beep_frequency = 0.0;
+            state.limiter_active = false;
         }
     }
 }
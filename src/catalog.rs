@@ -0,0 +1,133 @@
+use crate::game::Car;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Default cars shipped inside the binary so the game works from any directory
+const BUNDLED_CARS: &[(&str, &str)] = &[
+    ("civic.toml", include_str!("../cars/civic.toml")),
+    ("mustang.toml", include_str!("../cars/mustang.toml")),
+    ("gtr.toml", include_str!("../cars/gtr.toml")),
+];
+
+pub struct CarCatalog {
+    pub cars: Vec<Car>,
+    pub errors: Vec<CatalogError>,
+}
+
+#[derive(Debug)]
+pub struct CatalogError {
+    pub source: String,
+    pub message: String,
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.source, self.message)
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+impl CarCatalog {
+    /// Load the bundled cars, then any cars from the user's `cars/` directory.
+    /// A user car with the same name as a bundled one replaces it.
+    pub fn load() -> Self {
+        let mut catalog = Self::bundled();
+        if let Some(dir) = user_cars_dir() {
+            catalog.load_dir(&dir);
+        }
+        catalog
+    }
+
+    pub fn bundled() -> Self {
+        let mut catalog = Self {
+            cars: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        for (file_name, contents) in BUNDLED_CARS {
+            match parse_car(file_name, contents) {
+                Ok(car) => catalog.insert(car),
+                Err(message) => catalog.errors.push(CatalogError {
+                    source: format!("bundled {}", file_name),
+                    message,
+                }),
+            }
+        }
+
+        catalog
+    }
+
+    /// Load every `.toml` and `.json` file in `dir`, in file name order
+    pub fn load_dir(&mut self, dir: &Path) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            // A missing user directory just means no custom cars
+            Err(_) => return,
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| matches!(extension(path).as_deref(), Some("toml" | "json")))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let result = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| parse_car(&path.to_string_lossy(), &contents));
+
+            match result {
+                Ok(car) => self.insert(car),
+                Err(message) => self.errors.push(CatalogError {
+                    source: path.display().to_string(),
+                    message,
+                }),
+            }
+        }
+    }
+
+    fn insert(&mut self, car: Car) {
+        if let Some(existing) = self.cars.iter_mut().find(|c| c.name == car.name) {
+            *existing = car;
+        } else {
+            self.cars.push(car);
+        }
+    }
+}
+
+fn parse_car(file_name: &str, contents: &str) -> Result<Car, String> {
+    let car: Car = match extension(Path::new(file_name)).as_deref() {
+        Some("json") => serde_json::from_str(contents).map_err(|e| e.to_string())?,
+        _ => toml::from_str(contents).map_err(|e| e.to_string())?,
+    };
+    car.validate().map_err(|e| e.to_string())?;
+    Ok(car)
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+}
+
+/// Per-user configuration directory for drag-rs
+pub fn config_dir() -> Option<PathBuf> {
+    // Tests save replays and ghosts, so keep them out of the real config directory
+    if cfg!(test) {
+        let dir = format!("drag-rs-test-{}", std::process::id());
+        return Some(std::env::temp_dir().join(dir));
+    }
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("drag-rs"));
+    }
+    if let Some(dir) = std::env::var_os("APPDATA") {
+        return Some(PathBuf::from(dir).join("drag-rs"));
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("drag-rs"))
+}
+
+/// Directory users drop their own car files into
+pub fn user_cars_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("cars"))
+}
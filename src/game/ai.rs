@@ -1,29 +1,309 @@
-use super::{Car, RaceCarState};
+use super::{Car, RaceCarState, Transmission};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-pub struct AI {
+// Seconds of nitrous a burst fires after each gear change
+const NOS_BURST_LENGTH: f64 = 0.75;
+// Share of redline the AI shifts at to cool a hot engine, under the 90% where it heats
+const COOL_SHIFT_POINT: f64 = 0.85;
+// Throttle the AI lifts to when a hot engine is near the redline in top gear
+const HEAT_LIFT_THROTTLE: f64 = 0.6;
+// Seconds a fumbled shift costs on a gearbox that doesn't need the clutch to shift,
+// where the lever or paddle doesn't take first time
+const MISSED_SHIFT_TIME: f64 = 0.2;
+// Chance per race, scaled by the driver's heat tolerance, of ignoring the heat gauge
+const OVERCOOK_CHANCE: f64 = 0.1;
+// Whose nitrous and heat habits the perfect driver races with, whoever is drawn
+const PERFECT_PERSONALITY: Personality = Personality::Aggressive;
+
+/// How well the AI opponent drives
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum AiDifficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+    Pro,
+    /// Never misses: leaves on the green and shifts where the car's physics say is quickest
+    Perfect,
+}
+
+// What a difficulty level means behind the wheel
+struct Skill {
+    // Typical seconds after green the AI leaves, and how far either side it strays
     reaction_time: f64,
-    has_launched: bool,
+    reaction_spread: f64,
+    // Chance of guessing at the tree and leaving before green
+    red_light_chance: f64,
+    // Most RPM a shift lands either side of the shift point
     shift_timing_variance: u32,
-    nos_strategy: NosStrategy,
+    // Chance of fumbling each shift, or bogging the launch
+    mistake_chance: f64,
+    // Most seconds a bracket dial-in misjudges the car's clean-run ET, either way
+    dial_in_spread: f64,
+}
+
+impl AiDifficulty {
+    fn skill(self) -> Skill {
+        match self {
+            AiDifficulty::Easy => Skill {
+                reaction_time: 0.35,
+                reaction_spread: 0.15,
+                red_light_chance: 0.02,
+                shift_timing_variance: 800,
+                mistake_chance: 0.25,
+                dial_in_spread: 0.2,
+            },
+            AiDifficulty::Medium => Skill {
+                reaction_time: 0.15,
+                reaction_spread: 0.08,
+                red_light_chance: 0.05,
+                shift_timing_variance: 300,
+                mistake_chance: 0.05,
+                dial_in_spread: 0.12,
+            },
+            AiDifficulty::Hard => Skill {
+                reaction_time: 0.07,
+                reaction_spread: 0.04,
+                red_light_chance: 0.05,
+                shift_timing_variance: 150,
+                mistake_chance: 0.02,
+                dial_in_spread: 0.08,
+            },
+            // Pros don't fumble, but they cut the tree finest
+            AiDifficulty::Pro => Skill {
+                reaction_time: 0.03,
+                reaction_spread: 0.02,
+                red_light_chance: 0.08,
+                shift_timing_variance: 50,
+                mistake_chance: 0.0,
+                dial_in_spread: 0.05,
+            },
+            AiDifficulty::Perfect => Skill {
+                reaction_time: 0.0,
+                reaction_spread: 0.0,
+                red_light_chance: 0.0,
+                shift_timing_variance: 0,
+                mistake_chance: 0.0,
+                dial_in_spread: 0.0,
+            },
+        }
+    }
+}
+
+/// Driving style of the AI opponent, separate from how skilled it is
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Personality {
+    Cautious,
+    Aggressive,
+    #[default]
+    Consistent,
+    Showboat,
+    Tactician,
+}
+
+/// When the AI fires its nitrous
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NosStrategy {
+    /// From the hit, until it runs out
+    Launch,
+    /// Through the middle gears
+    Midrange,
+    /// In the final gears
+    Late,
+    /// A short hit after every gear change
+    Burst,
+    /// Whenever the tires are hooked up, cutting it when they spin
+    Adaptive,
+}
+
+impl Personality {
+    const ALL: [Personality; 5] = [
+        Personality::Cautious,
+        Personality::Aggressive,
+        Personality::Consistent,
+        Personality::Showboat,
+        Personality::Tactician,
+    ];
+
+    /// Pick the personality for a race
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.random_range(0..Self::ALL.len())]
+    }
+
+    /// The driver behind the personality
+    pub fn driver_name(self) -> &'static str {
+        match self {
+            Personality::Cautious => "Doc Haskins",
+            Personality::Aggressive => "Nitro Nash",
+            Personality::Consistent => "Steady Eddie",
+            Personality::Showboat => "Flash Farrell",
+            Personality::Tactician => "Professor Pike",
+        }
+    }
+
+    pub fn nos_strategy(self) -> NosStrategy {
+        match self {
+            Personality::Cautious => NosStrategy::Adaptive,
+            Personality::Aggressive => NosStrategy::Launch,
+            Personality::Consistent => NosStrategy::Late,
+            Personality::Showboat => NosStrategy::Burst,
+            Personality::Tactician => NosStrategy::Midrange,
+        }
+    }
+
+    /// Share of redline the driver aims to shift at
+    pub fn shift_point(self) -> f64 {
+        match self {
+            Personality::Cautious => 0.85,
+            Personality::Aggressive => 0.95,
+            Personality::Consistent => 0.88,
+            Personality::Showboat => 0.92,
+            Personality::Tactician => 0.9,
+        }
+    }
+
+    /// Engine heat the driver accepts before holding back the nitrous
+    pub fn heat_tolerance(self) -> f64 {
+        match self {
+            Personality::Cautious => 0.4,
+            Personality::Aggressive => 0.9,
+            Personality::Consistent => 0.6,
+            Personality::Showboat => 0.8,
+            Personality::Tactician => 0.6,
+        }
+    }
+
+    // Scales how far reactions and shifts stray from the driver's typical ones
+    fn consistency(self) -> f64 {
+        match self {
+            Personality::Aggressive | Personality::Showboat => 1.5,
+            Personality::Consistent => 0.5,
+            Personality::Cautious | Personality::Tactician => 1.0,
+        }
+    }
 }
 
-enum NosStrategy {
-    Late, // Use in final gears
+#[derive(Clone)]
+pub struct AI {
+    difficulty: AiDifficulty,
+    personality: Personality,
+    reaction_time: f64,
+    has_launched: bool,
+    // RPM either side of the shift point for each gear, drawn for the race
+    shift_offsets: Vec<i32>,
+    // Acceleration-maximizing shift RPM for each gear, used instead of the personality's
+    optimal_shift_rpms: Vec<u32>,
+    // Gears whose upshift gets fumbled: a manual is forced without the clutch, other
+    // gearboxes miss the shift
+    fumbled_shifts: Vec<bool>,
+    // Leaves on too few revs and bogs
+    bogged_launch: bool,
+    // Goes for it regardless of engine heat, and may blow up
+    overcooking: bool,
+    // Gear the car was in last update, and when the current nitrous burst ends
+    last_gear: u8,
+    nos_burst_end: f64,
 }
 
 impl AI {
-    pub fn new(reaction_time: f64) -> Self {
+    /// An AI that drives at its difficulty's typical pace, until `plan_race` rolls the race
+    pub fn new(difficulty: AiDifficulty, personality: Personality) -> Self {
         Self {
-            reaction_time,
+            difficulty,
+            personality,
+            reaction_time: difficulty.skill().reaction_time,
             has_launched: false,
-            shift_timing_variance: 200,
-            nos_strategy: NosStrategy::Late,
+            shift_offsets: Vec::new(),
+            optimal_shift_rpms: Vec::new(),
+            fumbled_shifts: Vec::new(),
+            bogged_launch: false,
+            overcooking: false,
+            last_gear: 0,
+            nos_burst_end: 0.0,
         }
     }
 
-    pub fn start_race(&mut self, state: &mut RaceCarState) {
-        // AI starts with its reaction time
+    pub fn personality(&self) -> Personality {
+        self.personality
+    }
+
+    /// A perfect driver changing up at `shift_rpms`, worked out from the car's physics
+    pub fn perfect_driver(shift_rpms: Vec<u32>) -> Self {
+        Self {
+            optimal_shift_rpms: shift_rpms,
+            ..Self::new(AiDifficulty::Perfect, PERFECT_PERSONALITY)
+        }
+    }
+
+    /// Whether this is the perfect driver, who ignores its personality's shift point
+    pub fn is_perfect(&self) -> bool {
+        self.difficulty == AiDifficulty::Perfect
+    }
+
+    /// RPM the AI changes up at in each gear, if it has worked them out from the physics
+    /// rather than shifting at its personality's shift point
+    pub fn optimal_shift_rpms(&self) -> &[u32] {
+        &self.optimal_shift_rpms
+    }
+
+    /// Decide how this race goes in `car`: the reaction time, where each shift lands
+    /// and any mistakes. Now and then the AI anticipates the tree and leaves before
+    /// green, which is a red light.
+    pub fn plan_race(&mut self, rng: &mut impl Rng, car: &Car) {
+        let skill = self.difficulty.skill();
+        let consistency = self.personality.consistency();
+
+        self.reaction_time = if rng.random_bool(skill.red_light_chance) {
+            -rng.random_range(0.005..0.1)
+        } else {
+            // Two uniform draws make a spread that bunches around the typical time
+            let spread = rng.random_range(-1.0..=1.0) + rng.random_range(-1.0..=1.0);
+            (skill.reaction_time + skill.reaction_spread * consistency * spread * 0.5).max(0.0)
+        };
+
+        let variance = (skill.shift_timing_variance as f64 * consistency) as i32;
+        self.shift_offsets = car
+            .gear_ratios
+            .iter()
+            .map(|_| rng.random_range(-variance..=variance))
+            .collect();
+        self.fumbled_shifts = car
+            .gear_ratios
+            .iter()
+            .map(|_| rng.random_bool(skill.mistake_chance))
+            .collect();
+        self.bogged_launch = rng.random_bool(skill.mistake_chance);
+        self.overcooking = rng.random_bool(self.personality.heat_tolerance() * OVERCOOK_CHANCE);
+
+        // A perfect driver never gambles with the engine
+        if self.is_perfect() {
+            self.overcooking = false;
+        }
+    }
+
+    /// Seconds the AI's bracket dial-in misses its clean-run ET by, positive when it
+    /// dials slower than it can run
+    pub fn dial_in_error(&self, rng: &mut impl Rng) -> f64 {
+        let spread = self.difficulty.skill().dial_in_spread;
+        rng.random_range(-spread..=spread)
+    }
+
+    /// Seconds after green the AI leaves, negative when it jumps the start
+    pub fn reaction_time(&self) -> f64 {
+        self.reaction_time
+    }
+
+    pub fn start_race(&mut self, state: &mut RaceCarState, car: &Car) {
+        // AI starts with its reaction time, leaving off the two-step unless it bogs
         state.reaction_time = Some(self.reaction_time);
+        state.rpm = if self.bogged_launch {
+            car.two_step_limit() / 2
+        } else {
+            car.two_step_limit()
+        };
+        state.clutch_rpm = state.rpm;
+        state.launch_control = !self.bogged_launch;
         state.throttle = 1.0;
         self.has_launched = true;
     }
@@ -34,24 +314,66 @@ impl AI {
             return;
         }
 
-        // Always on throttle
-        state.throttle = 1.0;
+        // Weigh heat against speed: once the engine is hotter than the driver likes,
+        // trade some pace to cool it, unless they've decided to go for it
+        let too_hot = !self.overcooking && state.engine_heat > self.personality.heat_tolerance();
+        let gear = state.gear as usize;
+        let top_gear = gear + 1 >= car.gear_ratios.len();
+
+        // Flat out, lifting only when a hot engine is running out of revs in top gear
+        let spinning = state.is_wheelspinning();
+        state.throttle =
+            if too_hot && top_gear && state.rpm > (car.redline as f64 * COOL_SHIFT_POINT) as u32 {
+                HEAT_LIFT_THROTTLE
+            } else {
+                1.0
+            };
 
-        // Shift logic
-        let optimal_shift_rpm = (car.redline as f64 * 0.88) as u32;
-        let shift_target = optimal_shift_rpm + self.shift_timing_variance;
+        // Shift logic, changing up early to keep a hot engine out of the high revs
+        let shift_point = if too_hot {
+            (car.redline as f64 * COOL_SHIFT_POINT) as i32
+        } else if let Some(&rpm) = self.optimal_shift_rpms.get(gear) {
+            rpm as i32
+        } else {
+            (car.redline as f64 * self.personality.shift_point()) as i32
+        };
+        let shift_offset = self.shift_offsets.get(gear).copied().unwrap_or(0);
+        let shift_target = (shift_point + shift_offset).max(0) as u32;
 
-        if state.rpm >= shift_target && state.gear < car.gear_ratios.len() as u8 - 1 {
-            state.shift_up(car);
+        // An automatic picks its own gears, and flared revs from wheelspin aren't a shift cue
+        if car.transmission != Transmission::Automatic
+            && !spinning
+            && state.rpm >= shift_target
+            && state.gear < car.gear_ratios.len() as u8 - 1
+        {
+            let fumbled = self.fumbled_shifts.get(gear).copied().unwrap_or(false);
+            state.shift_up(car, !fumbled);
         }
 
-        // NOS strategy - use in final gears
-        match self.nos_strategy {
-            NosStrategy::Late => {
-                if state.gear >= car.gear_ratios.len() as u8 - 2 && state.nos_remaining > 0.0 {
-                    state.nos_active = true;
-                }
+        // A fumbled shift the clutch can't be blamed for goes in late, and a fresh burst
+        // of nitrous follows each gear change
+        if state.gear != self.last_gear {
+            let fumbled = self
+                .fumbled_shifts
+                .get(self.last_gear as usize)
+                .copied()
+                .unwrap_or(false);
+            if fumbled && car.transmission != Transmission::Manual {
+                state.shift_timer += MISSED_SHIFT_TIME;
             }
+            self.last_gear = state.gear;
+            self.nos_burst_end = state.run_time + NOS_BURST_LENGTH;
         }
+
+        // NOS strategy, held back once the engine runs hotter than the driver likes
+        let gears = car.gear_ratios.len();
+        let wants_nos = match self.personality.nos_strategy() {
+            NosStrategy::Launch => true,
+            NosStrategy::Midrange => gear >= 1 && gear + 2 < gears,
+            NosStrategy::Late => gear + 2 >= gears,
+            NosStrategy::Burst => state.run_time < self.nos_burst_end,
+            NosStrategy::Adaptive => !spinning,
+        };
+        state.nos_active = wants_nos && state.nos_remaining > 0.0 && !too_hot;
     }
 }
@@ -1,4 +1,10 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Lowest redline accepted, leaving a usable rev range above the 1000 RPM idle
+const MIN_REDLINE: u32 = 3000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Car {
     pub name: String,
     pub horsepower: u32,
@@ -6,39 +12,579 @@ pub struct Car {
     pub torque: u32,
     pub redline: u32,
     pub gear_ratios: Vec<f64>,
+    /// Differential ratio applied on top of every gear; 1.0 treats `gear_ratios` as overall ratios
+    #[serde(default = "default_final_drive")]
+    pub final_drive: f64,
+    /// Driven tire diameter in meters
+    #[serde(default = "default_tire_diameter")]
+    pub tire_diameter: f64,
+    /// Sampled engine torque by RPM; empty means the generic efficiency curve is used
+    #[serde(default)]
+    pub torque_curve: Vec<TorquePoint>,
+    /// Tire friction coefficient; around 1.0 for street tires, higher for drag radials
+    #[serde(default = "default_tire_grip")]
+    pub tire_grip: f64,
+    #[serde(default)]
+    pub drivetrain: Drivetrain,
+    /// Share of the car's weight on the front axle at rest
+    #[serde(default = "default_front_weight")]
+    pub front_weight: f64,
+    /// Height of the center of gravity in meters, drives weight transfer under acceleration
+    #[serde(default = "default_cg_height")]
+    pub cg_height: f64,
+    /// Distance between the axles in meters
+    #[serde(default = "default_wheelbase")]
+    pub wheelbase: f64,
+    /// Aerodynamic drag coefficient
+    #[serde(default = "default_drag_coefficient")]
+    pub drag_coefficient: f64,
+    /// Frontal area in square meters, what the drag and downforce coefficients act on
+    #[serde(default = "default_frontal_area")]
+    pub frontal_area: f64,
+    /// Rolling resistance coefficient of the tires
+    #[serde(default = "default_rolling_resistance")]
+    pub rolling_resistance: f64,
+    /// Aerodynamic downforce coefficient; pushes the tires down harder as speed rises
+    #[serde(default)]
+    pub downforce_coefficient: f64,
+    /// RPM the launch control holds the engine at before launch; half the redline if unset
+    #[serde(default)]
+    pub two_step_rpm: Option<u32>,
+    #[serde(default)]
+    pub transmission: Transmission,
+    /// Seconds with no drive during an upshift; the transmission's usual time if unset
+    #[serde(default)]
+    pub shift_time: Option<f64>,
+}
+
+/// Which axle the engine drives
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Drivetrain {
+    Fwd,
+    #[default]
+    Rwd,
+    Awd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transmission {
+    /// H-pattern with a clutch pedal; forcing a shift without the clutch is slow
+    #[default]
+    Manual,
+    /// Shifts itself through a torque converter that slips and multiplies torque
+    Automatic,
+    /// Dog box that can be flat-shifted with the throttle held, clutch only for launch
+    Sequential,
+    /// Dual clutch, pre-selects the next gear and shifts almost instantly
+    Dct,
+}
+
+impl Transmission {
+    fn default_shift_time(self) -> f64 {
+        match self {
+            Transmission::Manual => 0.15,
+            Transmission::Automatic => 0.3,
+            Transmission::Sequential => 0.06,
+            Transmission::Dct => 0.02,
+        }
+    }
+
+    /// Whether the driver works a clutch pedal
+    pub fn has_clutch_pedal(self) -> bool {
+        matches!(self, Transmission::Manual | Transmission::Sequential)
+    }
+}
+
+fn default_tire_grip() -> f64 {
+    1.0
+}
+
+fn default_final_drive() -> f64 {
+    1.0
+}
+
+fn default_tire_diameter() -> f64 {
+    0.65
+}
+
+fn default_front_weight() -> f64 {
+    0.55
+}
+
+fn default_cg_height() -> f64 {
+    0.5
+}
+
+fn default_wheelbase() -> f64 {
+    2.7
+}
+
+fn default_drag_coefficient() -> f64 {
+    0.3
+}
+
+fn default_frontal_area() -> f64 {
+    2.2
+}
+
+fn default_rolling_resistance() -> f64 {
+    0.015
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TorquePoint {
+    pub rpm: u32,
+    pub torque: f64,
 }
 
 impl Car {
-    pub fn civic() -> Self {
-        Self {
-            name: "Honda Civic Si".to_string(),
-            horsepower: 200,
-            weight: 1300,
-            torque: 192,
-            redline: 8000,
-            gear_ratios: vec![3.267, 1.967, 1.367, 1.033, 0.833],
+    /// Check that the car can be simulated without producing nonsense or panicking
+    pub fn validate(&self) -> Result<(), CarError> {
+        if self.name.trim().is_empty() {
+            return Err(CarError::MissingName);
+        }
+        if self.weight == 0 {
+            return Err(CarError::ZeroWeight);
+        }
+        if self.redline < MIN_REDLINE {
+            return Err(CarError::RedlineTooLow(self.redline));
+        }
+        if self.gear_ratios.is_empty() {
+            return Err(CarError::NoGears);
+        }
+        if self.gear_ratios.len() > u8::MAX as usize {
+            return Err(CarError::TooManyGears(self.gear_ratios.len()));
+        }
+
+        for (index, &ratio) in self.gear_ratios.iter().enumerate() {
+            if !ratio.is_finite() || ratio <= 0.0 {
+                return Err(CarError::InvalidGearRatio {
+                    gear: index + 1,
+                    ratio,
+                });
+            }
+        }
+
+        // Each gear must be taller (numerically lower) than the one before it
+        for (index, pair) in self.gear_ratios.windows(2).enumerate() {
+            if pair[1] >= pair[0] {
+                return Err(CarError::GearsNotDescending { gear: index + 2 });
+            }
+        }
+
+        if !self.final_drive.is_finite() || self.final_drive <= 0.0 {
+            return Err(CarError::InvalidFinalDrive(self.final_drive));
+        }
+        if !self.tire_diameter.is_finite() || self.tire_diameter <= 0.0 {
+            return Err(CarError::InvalidTireDiameter(self.tire_diameter));
+        }
+        if !self.tire_grip.is_finite() || self.tire_grip <= 0.0 {
+            return Err(CarError::InvalidTireGrip(self.tire_grip));
+        }
+        if !(self.front_weight > 0.0 && self.front_weight < 1.0) {
+            return Err(CarError::InvalidFrontWeight(self.front_weight));
+        }
+        if !self.cg_height.is_finite() || self.cg_height <= 0.0 {
+            return Err(CarError::InvalidCgHeight(self.cg_height));
+        }
+        if !self.wheelbase.is_finite() || self.wheelbase <= 0.0 {
+            return Err(CarError::InvalidWheelbase(self.wheelbase));
+        }
+        if !self.drag_coefficient.is_finite() || self.drag_coefficient < 0.0 {
+            return Err(CarError::InvalidDragCoefficient(self.drag_coefficient));
         }
+        if !self.frontal_area.is_finite() || self.frontal_area <= 0.0 {
+            return Err(CarError::InvalidFrontalArea(self.frontal_area));
+        }
+        if !self.rolling_resistance.is_finite() || self.rolling_resistance < 0.0 {
+            return Err(CarError::InvalidRollingResistance(self.rolling_resistance));
+        }
+        if !self.downforce_coefficient.is_finite() || self.downforce_coefficient < 0.0 {
+            return Err(CarError::InvalidDownforce(self.downforce_coefficient));
+        }
+        if let Some(rpm) = self.two_step_rpm
+            && (rpm == 0 || rpm >= self.redline)
+        {
+            return Err(CarError::InvalidTwoStep(rpm));
+        }
+        if let Some(time) = self.shift_time
+            && (!time.is_finite() || time < 0.0)
+        {
+            return Err(CarError::InvalidShiftTime(time));
+        }
+
+        if self.torque_curve.len() == 1 {
+            return Err(CarError::TorqueCurveTooShort);
+        }
+        for point in &self.torque_curve {
+            if !point.torque.is_finite() || point.torque < 0.0 {
+                return Err(CarError::InvalidTorquePoint { rpm: point.rpm });
+            }
+        }
+        for pair in self.torque_curve.windows(2) {
+            if pair[1].rpm <= pair[0].rpm {
+                return Err(CarError::TorqueCurveNotAscending { rpm: pair[1].rpm });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Engine turns per wheel turn in `gear`, gearbox and differential together
+    pub fn overall_ratio(&self, gear: u8) -> f64 {
+        self.gear_ratios[gear as usize] * self.final_drive
+    }
+
+    /// RPM the two-step limiter holds while staged
+    pub fn two_step_limit(&self) -> u32 {
+        self.two_step_rpm.unwrap_or(self.redline / 2)
+    }
+
+    /// Seconds with no drive during an upshift
+    pub fn shift_duration(&self) -> f64 {
+        self.shift_time
+            .unwrap_or_else(|| self.transmission.default_shift_time())
+    }
+
+    /// Linearly interpolate the torque curve at `rpm`, holding the end values outside its range.
+    /// Returns `None` when the car has no torque curve.
+    pub fn torque_at(&self, rpm: u32) -> Option<f64> {
+        let first = self.torque_curve.first()?;
+        let last = self.torque_curve.last()?;
+
+        if rpm <= first.rpm {
+            return Some(first.torque);
+        }
+        if rpm >= last.rpm {
+            return Some(last.torque);
+        }
+
+        self.torque_curve.windows(2).find_map(|pair| {
+            let (low, high) = (pair[0], pair[1]);
+            if rpm > high.rpm {
+                return None;
+            }
+            let t = (rpm - low.rpm) as f64 / (high.rpm - low.rpm) as f64;
+            Some(low.torque + (high.torque - low.torque) * t)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CarError {
+    MissingName,
+    ZeroWeight,
+    RedlineTooLow(u32),
+    NoGears,
+    TooManyGears(usize),
+    InvalidGearRatio { gear: usize, ratio: f64 },
+    GearsNotDescending { gear: usize },
+    TorqueCurveTooShort,
+    InvalidTorquePoint { rpm: u32 },
+    TorqueCurveNotAscending { rpm: u32 },
+    InvalidFinalDrive(f64),
+    InvalidTireDiameter(f64),
+    InvalidTireGrip(f64),
+    InvalidFrontWeight(f64),
+    InvalidCgHeight(f64),
+    InvalidWheelbase(f64),
+    InvalidDragCoefficient(f64),
+    InvalidFrontalArea(f64),
+    InvalidRollingResistance(f64),
+    InvalidDownforce(f64),
+    InvalidTwoStep(u32),
+    InvalidShiftTime(f64),
+}
+
+impl fmt::Display for CarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CarError::MissingName => write!(f, "car has no name"),
+            CarError::ZeroWeight => write!(f, "weight must be greater than zero"),
+            CarError::RedlineTooLow(rpm) => {
+                write!(
+                    f,
+                    "redline must be at least {} RPM, got {}",
+                    MIN_REDLINE, rpm
+                )
+            }
+            CarError::NoGears => write!(f, "gear_ratios must contain at least one gear"),
+            CarError::TooManyGears(count) => {
+                write!(
+                    f,
+                    "gear_ratios has {} gears, at most 255 are supported",
+                    count
+                )
+            }
+            CarError::InvalidGearRatio { gear, ratio } => {
+                write!(f, "gear {} has invalid ratio {}", gear, ratio)
+            }
+            CarError::GearsNotDescending { gear } => write!(
+                f,
+                "gear {} ratio must be lower than the gear before it",
+                gear
+            ),
+            CarError::TorqueCurveTooShort => {
+                write!(f, "torque_curve needs at least two points")
+            }
+            CarError::InvalidTorquePoint { rpm } => {
+                write!(f, "torque_curve point at {} RPM has invalid torque", rpm)
+            }
+            CarError::TorqueCurveNotAscending { rpm } => write!(
+                f,
+                "torque_curve point at {} RPM must be higher than the point before it",
+                rpm
+            ),
+            CarError::InvalidFinalDrive(ratio) => {
+                write!(f, "final_drive must be greater than zero, got {}", ratio)
+            }
+            CarError::InvalidTireDiameter(diameter) => {
+                write!(
+                    f,
+                    "tire_diameter must be greater than zero, got {}",
+                    diameter
+                )
+            }
+            CarError::InvalidTireGrip(grip) => {
+                write!(f, "tire_grip must be greater than zero, got {}", grip)
+            }
+            CarError::InvalidFrontWeight(share) => {
+                write!(f, "front_weight must be between 0 and 1, got {}", share)
+            }
+            CarError::InvalidCgHeight(height) => {
+                write!(f, "cg_height must be greater than zero, got {}", height)
+            }
+            CarError::InvalidWheelbase(length) => {
+                write!(f, "wheelbase must be greater than zero, got {}", length)
+            }
+            CarError::InvalidDragCoefficient(cd) => {
+                write!(f, "drag_coefficient must be zero or more, got {}", cd)
+            }
+            CarError::InvalidFrontalArea(area) => {
+                write!(f, "frontal_area must be greater than zero, got {}", area)
+            }
+            CarError::InvalidRollingResistance(crr) => {
+                write!(f, "rolling_resistance must be zero or more, got {}", crr)
+            }
+            CarError::InvalidDownforce(cl) => {
+                write!(f, "downforce_coefficient must be zero or more, got {}", cl)
+            }
+            CarError::InvalidTwoStep(rpm) => {
+                write!(
+                    f,
+                    "two_step_rpm {} must be between zero and the redline",
+                    rpm
+                )
+            }
+            CarError::InvalidShiftTime(time) => {
+                write!(f, "shift_time must be zero or more, got {}", time)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CarError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::CarCatalog;
+
+    fn civic() -> Car {
+        CarCatalog::bundled().cars.remove(0)
     }
 
-    pub fn mustang() -> Self {
-        Self {
-            name: "Ford Mustang GT".to_string(),
-            horsepower: 450,
-            weight: 1700,
-            torque: 410,
-            redline: 7500,
-            gear_ratios: vec![3.66, 2.43, 1.69, 1.32, 1.00],
+    #[test]
+    fn bundled_cars_are_valid() {
+        for car in CarCatalog::bundled().cars {
+            assert_eq!(car.validate(), Ok(()), "{}", car.name);
         }
     }
 
-    pub fn gtr() -> Self {
-        Self {
-            name: "Nissan GT-R".to_string(),
-            horsepower: 565,
-            weight: 1740,
-            torque: 467,
-            redline: 7000,
-            gear_ratios: vec![4.056, 2.301, 1.595, 1.248, 1.001, 0.796],
+    #[test]
+    fn rejects_a_redline_too_close_to_idle() {
+        for redline in [0, 300, 1000, MIN_REDLINE - 1] {
+            let car = Car { redline, ..civic() };
+            assert_eq!(car.validate(), Err(CarError::RedlineTooLow(redline)));
         }
+        let car = Car {
+            redline: MIN_REDLINE,
+            two_step_rpm: None,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_missing_name_and_weight() {
+        let car = Car {
+            name: "  ".to_string(),
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::MissingName));
+        let car = Car {
+            weight: 0,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::ZeroWeight));
+    }
+
+    #[test]
+    fn rejects_bad_gear_ratios() {
+        let car = Car {
+            gear_ratios: vec![],
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::NoGears));
+        let car = Car {
+            gear_ratios: vec![3.0; 256],
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::TooManyGears(256)));
+        let car = Car {
+            gear_ratios: vec![3.0, -1.0],
+            ..civic()
+        };
+        assert_eq!(
+            car.validate(),
+            Err(CarError::InvalidGearRatio {
+                gear: 2,
+                ratio: -1.0
+            })
+        );
+        let car = Car {
+            gear_ratios: vec![3.0, 2.0, 2.0],
+            ..civic()
+        };
+        assert_eq!(
+            car.validate(),
+            Err(CarError::GearsNotDescending { gear: 3 })
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_drivetrain_and_chassis_values() {
+        let car = Car {
+            final_drive: 0.0,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidFinalDrive(0.0)));
+        let car = Car {
+            tire_diameter: -0.6,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidTireDiameter(-0.6)));
+        let car = Car {
+            tire_grip: 0.0,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidTireGrip(0.0)));
+        let car = Car {
+            front_weight: 1.0,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidFrontWeight(1.0)));
+        let car = Car {
+            cg_height: 0.0,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidCgHeight(0.0)));
+        let car = Car {
+            wheelbase: 0.0,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidWheelbase(0.0)));
+        let car = Car {
+            drag_coefficient: -0.1,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidDragCoefficient(-0.1)));
+        let car = Car {
+            frontal_area: 0.0,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidFrontalArea(0.0)));
+        let car = Car {
+            rolling_resistance: -0.01,
+            ..civic()
+        };
+        assert_eq!(
+            car.validate(),
+            Err(CarError::InvalidRollingResistance(-0.01))
+        );
+        let car = Car {
+            downforce_coefficient: -0.1,
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidDownforce(-0.1)));
+    }
+
+    #[test]
+    fn rejects_a_two_step_at_the_redline_and_a_negative_shift_time() {
+        let car = civic();
+        let redline = car.redline;
+        let car = Car {
+            two_step_rpm: Some(redline),
+            ..car
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidTwoStep(redline)));
+        let car = Car {
+            shift_time: Some(-0.1),
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::InvalidShiftTime(-0.1)));
+    }
+
+    #[test]
+    fn rejects_bad_torque_curves() {
+        let point = |rpm, torque| TorquePoint { rpm, torque };
+        let car = Car {
+            torque_curve: vec![point(1000, 100.0)],
+            ..civic()
+        };
+        assert_eq!(car.validate(), Err(CarError::TorqueCurveTooShort));
+        let car = Car {
+            torque_curve: vec![point(1000, 100.0), point(4000, f64::NAN)],
+            ..civic()
+        };
+        assert_eq!(
+            car.validate(),
+            Err(CarError::InvalidTorquePoint { rpm: 4000 })
+        );
+        let car = Car {
+            torque_curve: vec![point(4000, 100.0), point(4000, 120.0)],
+            ..civic()
+        };
+        assert_eq!(
+            car.validate(),
+            Err(CarError::TorqueCurveNotAscending { rpm: 4000 })
+        );
+    }
+
+    #[test]
+    fn torque_is_interpolated_between_points_and_held_past_the_ends() {
+        let point = |rpm, torque| TorquePoint { rpm, torque };
+        let car = Car {
+            torque_curve: vec![point(2000, 100.0), point(4000, 200.0), point(6000, 150.0)],
+            ..civic()
+        };
+
+        assert_eq!(car.torque_at(2000), Some(100.0));
+        assert_eq!(car.torque_at(3000), Some(150.0));
+        assert_eq!(car.torque_at(4000), Some(200.0));
+        assert_eq!(car.torque_at(5500), Some(162.5));
+        assert_eq!(car.torque_at(800), Some(100.0));
+        assert_eq!(car.torque_at(9000), Some(150.0));
+    }
+
+    #[test]
+    fn no_torque_curve_leaves_the_generic_curve_to_the_physics() {
+        let car = Car {
+            torque_curve: Vec::new(),
+            ..civic()
+        };
+        assert_eq!(car.torque_at(5000), None);
     }
 }
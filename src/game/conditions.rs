@@ -0,0 +1,319 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// Sea level on a dry 15°C day, the conditions the cars' power figures are rated at
+const STANDARD_PRESSURE: f64 = 101_325.0; // Pa
+const STANDARD_AIR_DENSITY: f64 = 1.225; // kg/m³
+
+const DRY_AIR_GAS_CONSTANT: f64 = 287.058; // J/(kg·K)
+const WATER_VAPOR_GAS_CONSTANT: f64 = 461.495; // J/(kg·K)
+
+// Track surface temperature the tires grip best at, and the grip lost per degree away from it
+const OPTIMAL_TRACK_TEMP: f64 = 40.0; // °C
+const TRACK_TEMP_GRIP_LOSS: f64 = 0.004;
+const MIN_TRACK_TEMP_GRIP: f64 = 0.8;
+
+// Weather the air and grip model is trusted with, well past anything a track runs in
+const TEMPERATURE_RANGE: RangeInclusive<f64> = -40.0..=60.0; // °C
+const ALTITUDE_RANGE: RangeInclusive<f64> = -500.0..=5000.0; // m
+const TRACK_TEMP_RANGE: RangeInclusive<f64> = -40.0..=90.0; // °C
+
+/// Weather and track surface for a session
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrackConditions {
+    /// Air temperature in °C
+    pub temperature: f64,
+    /// Track elevation in meters
+    pub altitude: f64,
+    /// Relative humidity, 0-1
+    pub humidity: f64,
+    /// Track surface temperature in °C
+    pub track_temp: f64,
+    pub prep: TrackPrep,
+}
+
+/// How much work has gone into the racing surface
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum TrackPrep {
+    /// Bare asphalt
+    Street,
+    /// Swept and rubbered in
+    #[default]
+    Prepped,
+    /// Sprayed with traction compound
+    Glued,
+}
+
+impl Default for TrackConditions {
+    /// A standard day, where the conditions leave the cars as rated
+    fn default() -> Self {
+        Self {
+            temperature: 15.0,
+            altitude: 0.0,
+            humidity: 0.0,
+            track_temp: OPTIMAL_TRACK_TEMP,
+            prep: TrackPrep::Prepped,
+        }
+    }
+}
+
+impl TrackConditions {
+    /// Conditions for a day at a random track
+    pub fn random(rng: &mut impl Rng) -> Self {
+        let temperature = rng.random_range(5.0..35.0);
+        let prep = match rng.random_range(0..4) {
+            0 => TrackPrep::Street,
+            3 => TrackPrep::Glued,
+            _ => TrackPrep::Prepped,
+        };
+
+        Self {
+            temperature,
+            altitude: rng.random_range(0.0..1600.0),
+            humidity: rng.random_range(0.2..0.9),
+            // Sun on the asphalt warms it past the air
+            track_temp: temperature + rng.random_range(5.0..25.0),
+            prep,
+        }
+    }
+
+    /// Check that conditions loaded from a file are weather the model can handle
+    pub fn validate(&self) -> Result<(), ConditionsError> {
+        if !TEMPERATURE_RANGE.contains(&self.temperature) {
+            return Err(ConditionsError::Temperature(self.temperature));
+        }
+        if !ALTITUDE_RANGE.contains(&self.altitude) {
+            return Err(ConditionsError::Altitude(self.altitude));
+        }
+        if !(0.0..=1.0).contains(&self.humidity) {
+            return Err(ConditionsError::Humidity(self.humidity));
+        }
+        if !TRACK_TEMP_RANGE.contains(&self.track_temp) {
+            return Err(ConditionsError::TrackTemp(self.track_temp));
+        }
+        Ok(())
+    }
+
+    /// Air density in kg/m³. Heat, altitude and humidity all thin the air.
+    pub fn air_density(&self) -> f64 {
+        let (dry, vapor) = self.partial_densities();
+        dry + vapor
+    }
+
+    /// Engine output relative to its rating. Engines burn the oxygen in the dry part
+    /// of the air, so this follows its density against a standard day.
+    pub fn power_factor(&self) -> f64 {
+        self.partial_densities().0 / STANDARD_AIR_DENSITY
+    }
+
+    /// Tire grip relative to a prepped track at the ideal surface temperature
+    pub fn grip_factor(&self) -> f64 {
+        let temp_grip = (1.0 - (self.track_temp - OPTIMAL_TRACK_TEMP).abs() * TRACK_TEMP_GRIP_LOSS)
+            .max(MIN_TRACK_TEMP_GRIP);
+        temp_grip * self.prep.grip_factor()
+    }
+
+    /// Density altitude in meters: the altitude of a standard day with the same air density
+    pub fn density_altitude(&self) -> f64 {
+        // Inverse of the standard atmosphere's density-altitude curve
+        44_330.8 * (1.0 - (self.air_density() / STANDARD_AIR_DENSITY).powf(0.234_969))
+    }
+
+    // Densities of the dry air and the water vapor, in kg/m³
+    fn partial_densities(&self) -> (f64, f64) {
+        let kelvin = self.temperature + 273.15;
+        // Standard atmosphere pressure at altitude
+        let pressure = STANDARD_PRESSURE * (1.0 - 2.255_77e-5 * self.altitude).powf(5.255_88);
+        // Tetens' saturation vapor pressure
+        let saturation = 610.78 * 10f64.powf(7.5 * self.temperature / (self.temperature + 237.3));
+        let vapor_pressure = self.humidity * saturation;
+
+        (
+            (pressure - vapor_pressure) / (DRY_AIR_GAS_CONSTANT * kelvin),
+            vapor_pressure / (WATER_VAPOR_GAS_CONSTANT * kelvin),
+        )
+    }
+}
+
+impl TrackPrep {
+    fn grip_factor(self) -> f64 {
+        match self {
+            TrackPrep::Street => 0.9,
+            TrackPrep::Prepped => 1.0,
+            TrackPrep::Glued => 1.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionsError {
+    Temperature(f64),
+    Altitude(f64),
+    Humidity(f64),
+    TrackTemp(f64),
+}
+
+impl fmt::Display for ConditionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConditionsError::Temperature(temp) => {
+                write!(
+                    f,
+                    "temperature {} °C is outside {:?}",
+                    temp, TEMPERATURE_RANGE
+                )
+            }
+            ConditionsError::Altitude(altitude) => {
+                write!(f, "altitude {} m is outside {:?}", altitude, ALTITUDE_RANGE)
+            }
+            ConditionsError::Humidity(humidity) => {
+                write!(f, "humidity must be between 0 and 1, got {}", humidity)
+            }
+            ConditionsError::TrackTemp(temp) => {
+                write!(
+                    f,
+                    "track_temp {} °C is outside {:?}",
+                    temp, TRACK_TEMP_RANGE
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConditionsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "{actual} vs {expected}"
+        );
+    }
+
+    #[test]
+    fn a_standard_day_leaves_the_cars_as_rated() {
+        let day = TrackConditions::default();
+        assert_near(day.air_density(), STANDARD_AIR_DENSITY, 0.001);
+        assert_near(day.power_factor(), 1.0, 0.001);
+        assert_near(day.grip_factor(), 1.0, 1e-12);
+        assert_near(day.density_altitude(), 0.0, 10.0);
+    }
+
+    #[test]
+    fn heat_altitude_and_humidity_thin_the_air_and_cost_power() {
+        let standard = TrackConditions::default();
+        let thinner = [
+            TrackConditions {
+                temperature: 35.0,
+                ..standard
+            },
+            TrackConditions {
+                altitude: 1600.0,
+                ..standard
+            },
+            TrackConditions {
+                humidity: 0.9,
+                ..standard
+            },
+        ];
+        for day in thinner {
+            assert!(day.air_density() < standard.air_density(), "{day:?}");
+            assert!(day.power_factor() < 1.0, "{day:?}");
+            assert!(day.density_altitude() > 0.0, "{day:?}");
+        }
+
+        // The standard atmosphere cools 6.5°C per 1000m, so air that cool at 1600m
+        // has a density altitude of 1600m, and warmer air reads higher
+        let standard_at_altitude = TrackConditions {
+            temperature: 15.0 - 6.5 * 1.6,
+            ..thinner[1]
+        };
+        assert_near(standard_at_altitude.density_altitude(), 1600.0, 10.0);
+        assert!(thinner[1].density_altitude() > 1600.0);
+        // At the same temperature, air at 1000m holds about 11% less oxygen
+        let kilometer_up = TrackConditions {
+            altitude: 1000.0,
+            ..standard
+        };
+        assert_near(kilometer_up.power_factor(), 0.887, 0.005);
+    }
+
+    #[test]
+    fn grip_falls_away_from_the_ideal_surface_temperature_and_follows_prep() {
+        let ideal = TrackConditions::default();
+        let cold = TrackConditions {
+            track_temp: OPTIMAL_TRACK_TEMP - 25.0,
+            ..ideal
+        };
+        let scorching = TrackConditions {
+            track_temp: OPTIMAL_TRACK_TEMP + 200.0,
+            ..ideal
+        };
+        assert_near(cold.grip_factor(), 0.9, 1e-12);
+        assert_near(scorching.grip_factor(), MIN_TRACK_TEMP_GRIP, 1e-12);
+
+        let street = TrackConditions {
+            prep: TrackPrep::Street,
+            ..ideal
+        };
+        let glued = TrackConditions {
+            prep: TrackPrep::Glued,
+            ..ideal
+        };
+        assert!(street.grip_factor() < ideal.grip_factor());
+        assert!(glued.grip_factor() > ideal.grip_factor());
+    }
+
+    #[test]
+    fn random_days_are_valid() {
+        let mut rng = rand::rng();
+        for _ in 0..100 {
+            let day = TrackConditions::random(&mut rng);
+            assert_eq!(day.validate(), Ok(()), "{day:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_weather_the_model_cannot_handle() {
+        let day = TrackConditions::default();
+        let bad = [
+            (
+                TrackConditions {
+                    temperature: f64::NAN,
+                    ..day
+                },
+                "temperature",
+            ),
+            (
+                TrackConditions {
+                    altitude: 50_000.0,
+                    ..day
+                },
+                "altitude",
+            ),
+            (
+                TrackConditions {
+                    humidity: -0.2,
+                    ..day
+                },
+                "humidity",
+            ),
+            (
+                TrackConditions {
+                    track_temp: f64::INFINITY,
+                    ..day
+                },
+                "track_temp",
+            ),
+        ];
+        for (conditions, field) in bad {
+            let error = conditions.validate().unwrap_err();
+            assert!(error.to_string().starts_with(field), "{error}");
+        }
+    }
+}
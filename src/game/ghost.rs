@@ -0,0 +1,60 @@
+use super::{Car, RaceCarState};
+use serde::{Deserialize, Serialize};
+
+/// How often the player's state is sampled while recording a ghost
+pub const GHOST_SAMPLE_INTERVAL: f64 = 0.02;
+
+/// Player lane state captured at a time measured from the green light
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhostSnapshot {
+    pub time: f64,
+    pub state: RaceCarState,
+}
+
+/// A recorded run that can drive the opponent lane in place of the AI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ghost {
+    pub car: Car,
+    pub et: f64,
+    /// Seconds after green the run left the line, independent of tree type
+    pub launch_delay: f64,
+    pub snapshots: Vec<GhostSnapshot>,
+}
+
+impl Ghost {
+    /// Move `state` to where the ghost was `time` seconds after green.
+    /// Position and velocity are interpolated between snapshots; the lane's own
+    /// timing (reaction, splits, finish) is left for the race to measure.
+    pub fn apply(&self, time: f64, state: &mut RaceCarState) {
+        let index = self.snapshots.partition_point(|s| s.time <= time);
+        let Some(before) = index.checked_sub(1).map(|i| &self.snapshots[i]) else {
+            return;
+        };
+
+        let (position, velocity) = match self.snapshots.get(index) {
+            Some(after) if after.time > before.time => {
+                let t = (time - before.time) / (after.time - before.time);
+                (
+                    before.state.position + (after.state.position - before.state.position) * t,
+                    before.state.velocity + (after.state.velocity - before.state.velocity) * t,
+                )
+            }
+            _ => (before.state.position, before.state.velocity),
+        };
+
+        let snapshot = &before.state;
+        state.position = position;
+        state.velocity = velocity;
+        state.wheel_speed = snapshot.wheel_speed.max(velocity);
+        state.rpm = snapshot.rpm;
+        state.gear = snapshot.gear;
+        state.throttle = snapshot.throttle;
+        state.nos_remaining = snapshot.nos_remaining;
+        state.nos_active = snapshot.nos_active;
+        state.engine_heat = snapshot.engine_heat;
+        state.top_speed = state.top_speed.max(velocity);
+        state.perfect_shifts = snapshot.perfect_shifts;
+        state.blown_engine = snapshot.blown_engine;
+        state.last_shift_quality = snapshot.last_shift_quality;
+    }
+}
@@ -1,9 +1,17 @@
 mod ai;
 mod car;
+mod conditions;
+mod ghost;
 mod physics;
 mod race;
+mod timing;
 
-pub use ai::AI;
-pub use car::Car;
+pub use ai::{AI, AiDifficulty, NosStrategy, Personality};
+pub use car::{Car, Drivetrain, Transmission};
+pub use conditions::{TrackConditions, TrackPrep};
+pub use ghost::{GHOST_SAMPLE_INTERVAL, Ghost, GhostSnapshot};
 pub use physics::ShiftQuality;
-pub use race::{LightState, RaceCarState, RaceState, Winner};
+pub use race::{
+    ChristmasTree, LaneOutcome, LightState, PerfectRun, RaceCarState, RaceMode, RaceSettings,
+    RaceState, ThrottleResponse, TreeType, Winner,
+};
@@ -1,14 +1,39 @@
-use super::Car;
+use super::{Car, Drivetrain, TrackConditions, Transmission};
+use serde::{Deserialize, Serialize};
 
-const DRAG_COEFFICIENT: f64 = 0.3;
-const AIR_DENSITY: f64 = 1.225;
-const FRONTAL_AREA: f64 = 2.2;
-const ROLLING_RESISTANCE: f64 = 0.015;
 const GRAVITY: f64 = 9.81;
 
-pub fn calculate_acceleration(
+// Car torque figures are in lb-ft
+const LB_FT_TO_NM: f64 = 1.3558;
+// Share of engine torque that survives the gearbox and differential
+const DRIVETRAIN_EFFICIENCY: f64 = 0.85;
+
+// Slip ratio the tires hold before breaking loose
+const PEAK_SLIP_RATIO: f64 = 0.15;
+// Share of peak grip left once the tires are spinning freely
+const SLIDING_GRIP: f64 = 0.7;
+const SLIP_MIN_SPEED: f64 = 1.0; // m/s
+// Wheels, axles and driveline spun up by wheelspin, as an equivalent mass at the tire
+const ROTATING_MASS: f64 = 200.0; // kg
+
+// Seconds a perfect shift's boost lasts, and the share of redline it must land in
+pub const PERFECT_SHIFT_BOOST_TIME: f64 = 0.5;
+const PERFECT_SHIFT_WINDOW: (f64, f64) = (0.85, 0.92);
+
+// RPM step when comparing gears for the best shift point
+const SHIFT_SEARCH_RPM_STEP: usize = 25;
+
+// Torque converter stall speed at full throttle, as a share of redline
+const CONVERTER_STALL_FRACTION: f64 = 0.35;
+// Torque multiplication with the turbine held still
+const CONVERTER_STALL_TORQUE_RATIO: f64 = 2.0;
+// Turbine to engine speed ratio where the converter couples and stops multiplying
+const CONVERTER_COUPLING_POINT: f64 = 0.85;
+
+/// Forward force the engine pushes through the driven wheels, before any grip limit
+pub fn calculate_drive_force(
     car: &Car,
-    velocity: f64,
+    conditions: &TrackConditions,
     rpm: u32,
     gear: u8,
     throttle: f64,
@@ -19,17 +44,15 @@ pub fn calculate_acceleration(
         return 0.0;
     }
 
-    let gear_ratio = car.gear_ratios[gear as usize];
+    // Engine force based on the car's torque curve, corrected for the air it breathes
+    let base_torque =
+        calculate_engine_torque(car, rpm) * LB_FT_TO_NM * conditions.power_factor() * throttle;
 
-    // Engine force based on RPM efficiency curve
-    let rpm_efficiency = calculate_rpm_efficiency(rpm, car.redline);
-    let base_torque = car.torque as f64 * rpm_efficiency * throttle;
+    // Gearbox and differential multiplication
+    let wheel_torque = base_torque * car.overall_ratio(gear) * DRIVETRAIN_EFFICIENCY;
 
-    // Gear multiplication
-    let wheel_torque = base_torque * gear_ratio;
-
-    // Convert to force (simplified)
-    let mut engine_force = wheel_torque * 3.5;
+    // Torque at the axle pushes the car through the tire's contact patch
+    let mut engine_force = wheel_torque / (car.tire_diameter / 2.0);
 
     // Nitrous boost
     if nos_active {
@@ -41,28 +64,178 @@ pub fn calculate_acceleration(
         engine_force *= 1.15;
     }
 
+    engine_force
+}
+
+/// Torque multiplication from an automatic's converter, given the turbine (wheel
+/// side) and engine RPM. Strongest at stall, none once the converter couples.
+pub fn calculate_converter_torque_ratio(car: &Car, turbine_rpm: u32, engine_rpm: u32) -> f64 {
+    if car.transmission != Transmission::Automatic {
+        return 1.0;
+    }
+
+    let speed_ratio = turbine_rpm as f64 / engine_rpm.max(1) as f64;
+    if speed_ratio >= CONVERTER_COUPLING_POINT {
+        1.0
+    } else {
+        CONVERTER_STALL_TORQUE_RATIO
+            - (CONVERTER_STALL_TORQUE_RATIO - 1.0) * speed_ratio / CONVERTER_COUPLING_POINT
+    }
+}
+
+/// Engine RPM behind an automatic's converter, which slips up towards the stall
+/// speed under throttle until the wheels catch up
+pub fn calculate_converter_rpm(car: &Car, turbine_rpm: u32, throttle: f64) -> u32 {
+    let stall_rpm = car.redline as f64 * CONVERTER_STALL_FRACTION * throttle;
+    turbine_rpm.max(stall_rpm as u32)
+}
+
+/// Net acceleration when the tires pass on as much of `drive_force` as `traction` allows
+pub fn calculate_acceleration(
+    car: &Car,
+    conditions: &TrackConditions,
+    velocity: f64,
+    drive_force: f64,
+    traction: f64,
+) -> f64 {
+    let tire_force = drive_force.min(traction);
+
     // Drag force (increases with velocity squared)
-    let drag_force = 0.5 * AIR_DENSITY * DRAG_COEFFICIENT * FRONTAL_AREA * velocity.powi(2);
+    let drag_force =
+        0.5 * conditions.air_density() * car.drag_coefficient * car.frontal_area * velocity.powi(2);
 
-    // Rolling resistance
-    let rolling_force = ROLLING_RESISTANCE * car.weight as f64 * GRAVITY;
+    // Rolling resistance, from the car's weight plus whatever the aero pushes down
+    let rolling_force = car.rolling_resistance
+        * (car.weight as f64 * GRAVITY + calculate_downforce(car, conditions, velocity));
 
     // Net force and acceleration
-    let net_force = engine_force - drag_force - rolling_force;
+    let net_force = tire_force - drag_force - rolling_force;
     net_force / car.weight as f64
 }
 
-pub fn calculate_rpm(velocity: f64, gear_ratio: f64) -> u32 {
-    // Wheel RPM based on velocity
-    let tire_diameter = 0.65; // meters
-    let tire_circumference = std::f64::consts::PI * tire_diameter;
+/// Aerodynamic downforce in newtons at `velocity`
+pub fn calculate_downforce(car: &Car, conditions: &TrackConditions, velocity: f64) -> f64 {
+    0.5 * conditions.air_density() * car.downforce_coefficient * car.frontal_area * velocity.powi(2)
+}
+
+/// Full-throttle acceleration in `gear` at `velocity` with the tires hooked up
+fn calculate_gear_acceleration(
+    car: &Car,
+    conditions: &TrackConditions,
+    velocity: f64,
+    gear: u8,
+) -> f64 {
+    let rpm = calculate_rpm(car, velocity, gear);
+    let drive_force = calculate_drive_force(car, conditions, rpm, gear, 1.0, false, 0.0);
+
+    // Weight transfer depends on the acceleration itself, so settle it with a second pass
+    let static_traction = calculate_traction(car, conditions, 0.0, velocity, 0.0);
+    let estimate = calculate_acceleration(car, conditions, velocity, drive_force, static_traction);
+    let traction = calculate_traction(car, conditions, 0.0, velocity, estimate);
+    calculate_acceleration(car, conditions, velocity, drive_force, traction)
+}
+
+/// RPM to leave each gear at for the most acceleration, one per upshift. Comparing the
+/// two gears at the same road speed, the car changes up once the next gear pulls
+/// harder than the current one, or at the redline if it never does.
+pub fn calculate_optimal_shift_rpms(car: &Car, conditions: &TrackConditions) -> Vec<u32> {
+    // First gear starts from rest, the others from where the last shift left the car
+    let mut entry_velocity = 0.0;
+    (0..car.gear_ratios.len() as u8 - 1)
+        .map(|gear| {
+            let entry_rpm = calculate_rpm(car, entry_velocity, gear).min(car.redline);
+            let shift_rpm = (entry_rpm..=car.redline)
+                .step_by(SHIFT_SEARCH_RPM_STEP)
+                .find(|&rpm| {
+                    let velocity = calculate_gear_velocity(car, rpm, gear);
+                    calculate_gear_acceleration(car, conditions, velocity, gear + 1)
+                        > calculate_gear_acceleration(car, conditions, velocity, gear)
+                })
+                .unwrap_or(car.redline);
+            entry_velocity = calculate_gear_velocity(car, shift_rpm, gear);
+            shift_rpm
+        })
+        .collect()
+}
+
+/// How much faster the driven tires' surface is moving than the car, as a fraction of car speed
+pub fn calculate_slip_ratio(wheel_speed: f64, velocity: f64) -> f64 {
+    // Measure against a minimum speed so slip stays finite when launching from rest
+    (wheel_speed - velocity) / velocity.max(SLIP_MIN_SPEED)
+}
+
+/// Load on the driven wheels in newtons. Accelerating shifts weight off the front
+/// axle onto the rear, unloading a front-drive car's tires; downforce adds to both
+/// axles in proportion to the static weight split.
+pub fn calculate_driven_load(
+    car: &Car,
+    conditions: &TrackConditions,
+    velocity: f64,
+    acceleration: f64,
+) -> f64 {
+    let weight = car.weight as f64 * GRAVITY + calculate_downforce(car, conditions, velocity);
+    let transfer = car.weight as f64 * acceleration * car.cg_height / car.wheelbase;
+    let front_load = (weight * car.front_weight - transfer).clamp(0.0, weight);
+
+    match car.drivetrain {
+        Drivetrain::Fwd => front_load,
+        Drivetrain::Rwd => weight - front_load,
+        Drivetrain::Awd => weight,
+    }
+}
+
+/// Most forward force the driven tires can hold at `slip_ratio` while the car is
+/// moving at `velocity` and accelerating at `acceleration`. Grip is full until the
+/// tires break loose, then falls away the faster they spin. The track surface scales
+/// the tires' grip.
+pub fn calculate_traction(
+    car: &Car,
+    conditions: &TrackConditions,
+    slip_ratio: f64,
+    velocity: f64,
+    acceleration: f64,
+) -> f64 {
+    let grip = car.tire_grip * conditions.grip_factor();
+    let peak = grip * calculate_driven_load(car, conditions, velocity, acceleration);
+    if slip_ratio <= PEAK_SLIP_RATIO {
+        peak
+    } else {
+        peak * (1.0 - (slip_ratio - PEAK_SLIP_RATIO) * 0.5).max(SLIDING_GRIP)
+    }
+}
+
+/// How quickly the driven wheels' surface speed changes when the drive force exceeds
+/// the traction (spinning up) or falls below it (hooking back up)
+pub fn calculate_wheel_acceleration(drive_force: f64, traction: f64) -> f64 {
+    (drive_force - traction) / ROTATING_MASS
+}
+
+pub fn is_wheelspin(slip_ratio: f64) -> bool {
+    slip_ratio > PEAK_SLIP_RATIO
+}
+
+pub fn calculate_rpm(car: &Car, velocity: f64, gear: u8) -> u32 {
+    // Wheel RPM based on the driven wheels' surface speed
+    let tire_circumference = std::f64::consts::PI * car.tire_diameter;
     let wheel_rpm = (velocity * 60.0) / tire_circumference;
 
     // Engine RPM
-    let engine_rpm = wheel_rpm * gear_ratio;
+    let engine_rpm = wheel_rpm * car.overall_ratio(gear);
     engine_rpm.max(800.0) as u32
 }
 
+/// Road speed in m/s at which the engine turns `rpm` in `gear`
+fn calculate_gear_velocity(car: &Car, rpm: u32, gear: u8) -> f64 {
+    let tire_circumference = std::f64::consts::PI * car.tire_diameter;
+    rpm as f64 / car.overall_ratio(gear) * tire_circumference / 60.0
+}
+
+/// Torque at `rpm` from the car's own curve, or the generic efficiency curve if it has none
+pub fn calculate_engine_torque(car: &Car, rpm: u32) -> f64 {
+    car.torque_at(rpm)
+        .unwrap_or_else(|| car.torque as f64 * calculate_rpm_efficiency(rpm, car.redline))
+}
+
 fn calculate_rpm_efficiency(rpm: u32, redline: u32) -> f64 {
     let rpm_percent = rpm as f64 / redline as f64;
 
@@ -81,12 +254,12 @@ fn calculate_rpm_efficiency(rpm: u32, redline: u32) -> f64 {
 }
 
 pub fn calculate_shift_quality(rpm: u32, redline: u32) -> ShiftQuality {
-    let optimal_start = (redline as f64 * 0.85) as u32;
-    let optimal_end = (redline as f64 * 0.92) as u32;
+    let optimal_start = (redline as f64 * PERFECT_SHIFT_WINDOW.0) as u32;
+    let optimal_end = (redline as f64 * PERFECT_SHIFT_WINDOW.1) as u32;
 
     if rpm >= optimal_start && rpm <= optimal_end {
         ShiftQuality::Perfect
-    } else if rpm >= optimal_start - 300 && rpm <= optimal_end + 300 {
+    } else if rpm >= optimal_start.saturating_sub(300) && rpm <= optimal_end + 300 {
         ShiftQuality::Good
     } else if rpm < 3000 {
         ShiftQuality::TooEarly
@@ -95,10 +268,74 @@ pub fn calculate_shift_quality(rpm: u32, redline: u32) -> ShiftQuality {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ShiftQuality {
     Perfect,
     Good,
     Missed,
     TooEarly,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::CarCatalog;
+    use crate::game::car::TorquePoint;
+
+    #[test]
+    fn optimal_shifts_fall_between_peak_torque_and_redline() {
+        for car in CarCatalog::bundled().cars {
+            let peak_torque_rpm = car
+                .torque_curve
+                .iter()
+                .max_by(|a, b| a.torque.total_cmp(&b.torque))
+                .unwrap()
+                .rpm;
+            let shift_rpms = calculate_optimal_shift_rpms(&car, &TrackConditions::default());
+
+            assert_eq!(shift_rpms.len(), car.gear_ratios.len() - 1, "{}", car.name);
+            for rpm in shift_rpms {
+                assert!(
+                    rpm > peak_torque_rpm && rpm <= car.redline,
+                    "{} shifts at {} RPM",
+                    car.name,
+                    rpm
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shifts_before_the_redline_once_the_next_gear_pulls_harder() {
+        // Torque falls away sharply past 6000 RPM, so holding a gear to the redline loses
+        let mut car = CarCatalog::bundled().cars.remove(0);
+        car.torque_curve = vec![
+            TorquePoint {
+                rpm: 1000,
+                torque: 150.0,
+            },
+            TorquePoint {
+                rpm: 6000,
+                torque: 150.0,
+            },
+            TorquePoint {
+                rpm: 8000,
+                torque: 20.0,
+            },
+        ];
+
+        for rpm in calculate_optimal_shift_rpms(&car, &TrackConditions::default()) {
+            assert!(rpm > 6000 && rpm < car.redline, "shifts at {} RPM", rpm);
+        }
+    }
+
+    #[test]
+    fn grades_shifts_around_the_perfect_window() {
+        assert_eq!(calculate_shift_quality(7000, 8000), ShiftQuality::Perfect);
+        assert_eq!(calculate_shift_quality(6600, 8000), ShiftQuality::Good);
+        assert_eq!(calculate_shift_quality(2500, 8000), ShiftQuality::TooEarly);
+        assert_eq!(calculate_shift_quality(8000, 8000), ShiftQuality::Missed);
+        // The window's lower margin reaches below zero instead of underflowing
+        assert_eq!(calculate_shift_quality(0, 300), ShiftQuality::Good);
+    }
+}
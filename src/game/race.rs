@@ -1,10 +1,39 @@
+use super::ghost::Ghost;
 use super::physics::{
-    ShiftQuality, calculate_acceleration, calculate_rpm, calculate_shift_quality,
+    PERFECT_SHIFT_BOOST_TIME, ShiftQuality, calculate_acceleration, calculate_converter_rpm,
+    calculate_converter_torque_ratio, calculate_drive_force, calculate_optimal_shift_rpms,
+    calculate_rpm, calculate_shift_quality, calculate_slip_ratio, calculate_traction,
+    calculate_wheel_acceleration, is_wheelspin,
 };
-use super::{AI, Car};
-use std::time::Instant;
+use super::timing::TimingSlip;
+use super::{AI, AiDifficulty, Car, Personality, TrackConditions, Transmission};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
 const FINISH_LINE: f64 = 402.336; // Quarter mile in meters
+// Longest random hold at staged, so drivers can't time the ambers from staging
+const MAX_STAGING_DELAY: f64 = 1.0;
+const IDLE_RPM: u32 = 1000;
+// How fast the engine revs up or down with no load on it, before launch
+const FREE_REV_RATE: f64 = 10000.0; // RPM per second
+// How long a slipping clutch takes to pull held revs down through the car's whole rev
+// range once it's let out, so it takes up a high-revving engine as smoothly as a lazy one
+const CLUTCH_TAKE_UP_TIME: f64 = 2.5; // seconds
+// Extra time with no drive when forcing a manual's lever through without the clutch
+const FORCED_SHIFT_PENALTY: f64 = 0.2;
+// Share of redline where an automatic changes up on its own
+const AUTOMATIC_UPSHIFT_FRACTION: f64 = 0.9;
+// Share of redline above which the engine heats up
+const OVER_REV_FRACTION: f64 = 0.9;
+// Engine heat gained per second over-revving, lost per second below it, and added per
+// second by nitrous on top of either
+const OVER_REV_HEAT_RATE: f64 = 0.3;
+const COOLING_RATE: f64 = 0.1;
+const NOS_HEAT_RATE: f64 = 0.15;
+// How far under its expected ET the AI dials in, on top of its misjudgement, to avoid
+// breaking out
+const AI_DIAL_IN_MARGIN: f64 = 0.03;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LightState {
@@ -23,68 +52,215 @@ pub enum Winner {
     Opponent,
 }
 
+/// How a lane's run ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LaneOutcome {
+    /// Crossed the finish line
+    Finished,
+    /// Blew the engine before the finish line
+    DnfBlown,
+    /// Left before green
+    RedLight,
+    /// Crossed the finish line quicker than the bracket dial-in
+    Breakout,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum RaceMode {
+    /// First to the finish line wins
+    #[default]
+    HeadsUp,
+    /// Handicap race: the slower dial-in leaves first and running under your dial is a breakout
+    Bracket {
+        player_dial: f64,
+        opponent_dial: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum TreeType {
+    /// Ambers light one after another 0.5s apart, green 0.5s after the last
+    #[default]
+    Sportsman,
+    /// All three ambers light together, green 0.4s later
+    Pro,
+}
+
+impl TreeType {
+    /// Reaction time of a driver leaving exactly on green. Reaction is timed from the
+    /// last amber, so anything quicker than this is a red light.
+    pub fn perfect_reaction(self) -> f64 {
+        match self {
+            TreeType::Sportsman => 0.5,
+            TreeType::Pro => 0.4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ThrottleResponse {
+    /// Pedal jumps straight to position, the original on/off feel
+    #[default]
+    Instant,
+    /// Full throttle in about an eighth of a second
+    Quick,
+    /// Full throttle in half a second, easier to feather a launch
+    Progressive,
+}
+
+impl ThrottleResponse {
+    /// How fast the pedal opens and closes, as (rise, fall) in full travel per second
+    fn rates(self) -> (f64, f64) {
+        match self {
+            ThrottleResponse::Instant => (f64::INFINITY, f64::INFINITY),
+            ThrottleResponse::Quick => (8.0, 12.0),
+            ThrottleResponse::Progressive => (2.0, 4.0),
+        }
+    }
+}
+
+/// Options chosen before the race that replays need to reproduce it
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct RaceSettings {
+    pub mode: RaceMode,
+    pub tree: TreeType,
+    #[serde(default)]
+    pub throttle: ThrottleResponse,
+    #[serde(default)]
+    pub conditions: TrackConditions,
+    #[serde(default)]
+    pub difficulty: AiDifficulty,
+}
+
+impl RaceMode {
+    /// Extra time each lane's tree holds at staged before the ambers, as (player, opponent)
+    fn start_delays(&self) -> (f64, f64) {
+        match *self {
+            RaceMode::HeadsUp => (0.0, 0.0),
+            RaceMode::Bracket {
+                player_dial,
+                opponent_dial,
+            } => (
+                (opponent_dial - player_dial).max(0.0),
+                (player_dial - opponent_dial).max(0.0),
+            ),
+        }
+    }
+}
+
 pub struct ChristmasTree {
     pub state: LightState,
-    state_timer: f64,
+    pub tree_type: TreeType,
+    // Time since the tree started, runs alongside the race's simulation clock
+    elapsed: f64,
+    staging_delay: f64,
 }
 
 impl ChristmasTree {
-    fn new() -> Self {
+    fn new(tree_type: TreeType, staging_delay: f64) -> Self {
         Self {
             state: LightState::PreStage,
-            state_timer: 0.0,
+            tree_type,
+            elapsed: 0.0,
+            staging_delay,
         }
     }
 
-    fn update(&mut self, delta_time: f64) -> bool {
-        self.state_timer += delta_time;
+    fn update(&mut self, delta_time: f64) {
+        self.elapsed += delta_time;
 
-        match self.state {
-            LightState::PreStage if self.state_timer >= 0.5 => {
-                self.state = LightState::Staged;
-                self.state_timer = 0.0;
-            }
-            LightState::Staged if self.state_timer >= 0.5 => {
-                self.state = LightState::Yellow1;
-                self.state_timer = 0.0;
-            }
-            LightState::Yellow1 if self.state_timer >= 0.5 => {
-                self.state = LightState::Yellow2;
-                self.state_timer = 0.0;
-            }
-            LightState::Yellow2 if self.state_timer >= 0.5 => {
-                self.state = LightState::Yellow3;
-                self.state_timer = 0.0;
-            }
-            LightState::Yellow3 if self.state_timer >= 0.5 => {
-                self.state = LightState::Green;
-                self.state_timer = 0.0;
-                return true; // Race can start
-            }
-            // Don't auto-transition to Racing - let the race logic handle this
-            _ => {}
+        // Don't leave Racing - once the lane has launched the race logic owns the state
+        if self.state == LightState::Racing {
+            return;
         }
-        false
+
+        let ambers = self.ambers_time();
+        self.state = if self.elapsed >= self.green_time() {
+            LightState::Green
+        } else if self.elapsed >= ambers {
+            // A pro tree lights all three ambers at once
+            match self.tree_type {
+                TreeType::Sportsman => match ((self.elapsed - ambers) / 0.5) as u32 {
+                    0 => LightState::Yellow1,
+                    1 => LightState::Yellow2,
+                    _ => LightState::Yellow3,
+                },
+                TreeType::Pro => LightState::Yellow3,
+            }
+        } else if self.elapsed >= 0.5 {
+            LightState::Staged
+        } else {
+            LightState::PreStage
+        };
+    }
+
+    // When the first amber lights
+    fn ambers_time(&self) -> f64 {
+        1.0 + self.staging_delay
+    }
+
+    /// Simulation time this lane's green light comes on. The sequence is fixed once
+    /// the tree is built, so a launch can be timed against it before it happens.
+    pub fn green_time(&self) -> f64 {
+        let last_amber = match self.tree_type {
+            TreeType::Sportsman => self.ambers_time() + 1.0,
+            TreeType::Pro => self.ambers_time(),
+        };
+        last_amber + self.tree_type.perfect_reaction()
+    }
+
+    /// Whether the ambers are lit or the light is green, so a launch now counts
+    fn is_running(&self) -> bool {
+        !matches!(self.state, LightState::PreStage | LightState::Staged)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RaceCarState {
     pub position: f64,
     pub velocity: f64,
+    /// Surface speed of the driven tires, ahead of `velocity` while they spin
+    #[serde(default)]
+    pub wheel_speed: f64,
+    /// Acceleration over the last step, which sets how much weight has transferred
+    #[serde(default)]
+    pub acceleration: f64,
     pub rpm: u32,
+    /// Revs held by the slipping clutch after it's let out, dropping until the wheels catch up
+    #[serde(default)]
+    pub clutch_rpm: u32,
+    /// Clutch pedal down, disconnecting the engine from the wheels
+    #[serde(default)]
+    pub clutch_in: bool,
+    /// Left the line on launch control, which trims the drive to what the tires can
+    /// hold until the clutch hooks up
+    #[serde(default)]
+    pub launch_control: bool,
+    /// Time left in the current gear change, during which no torque is delivered
+    #[serde(default)]
+    pub shift_timer: f64,
     pub gear: u8,
     pub throttle: f64,
     pub nos_remaining: f64,
     pub nos_active: bool,
     pub engine_heat: f64,
     pub reaction_time: Option<f64>,
+    /// Left before this lane's green light
+    #[serde(default)]
+    pub red_light: bool,
+    /// Simulation time the car left the line
+    pub launch_time: Option<f64>,
+    /// Time since the car left the line, stopped at the finish
+    pub run_time: f64,
+    /// Elapsed time from leaving the line to crossing the finish
     pub finish_time: Option<f64>,
     pub top_speed: f64,
     pub perfect_shifts: u8,
     pub blown_engine: bool,
     pub perfect_shift_boost: f64,
     pub last_shift_quality: Option<ShiftQuality>,
+    #[serde(skip)]
+    pub slip: TimingSlip,
 }
 
 impl RaceCarState {
@@ -92,24 +268,48 @@ impl RaceCarState {
         Self {
             position: 0.0,
             velocity: 0.0,
-            rpm: 1000,
+            wheel_speed: 0.0,
+            acceleration: 0.0,
+            rpm: IDLE_RPM,
+            clutch_rpm: 0,
+            clutch_in: false,
+            launch_control: false,
+            shift_timer: 0.0,
             gear: 0,
             throttle: 0.0,
             nos_remaining: 10.0,
             nos_active: false,
             engine_heat: 0.0,
             reaction_time: None,
+            red_light: false,
+            launch_time: None,
+            run_time: 0.0,
             finish_time: None,
             top_speed: 0.0,
             perfect_shifts: 0,
             blown_engine: false,
             perfect_shift_boost: 0.0,
             last_shift_quality: None,
+            slip: TimingSlip::default(),
         }
     }
 
-    pub fn shift_up(&mut self, car: &Car) {
-        if self.gear >= car.gear_ratios.len() as u8 - 1 {
+    pub fn is_wheelspinning(&self) -> bool {
+        is_wheelspin(calculate_slip_ratio(self.wheel_speed, self.velocity))
+    }
+
+    /// Let the clutch out. Revs built while it was in are held by the slipping clutch.
+    pub fn release_clutch(&mut self) {
+        if self.clutch_in {
+            self.clutch_in = false;
+            self.clutch_rpm = self.rpm;
+        }
+    }
+
+    /// Change up a gear. Drive is cut for the shift; a manual is slower to force
+    /// through without the clutch, the other transmissions don't need it.
+    pub fn shift_up(&mut self, car: &Car, with_clutch: bool) {
+        if self.gear >= car.gear_ratios.len() as u8 - 1 || self.shift_timer > 0.0 {
             return;
         }
 
@@ -118,42 +318,151 @@ impl RaceCarState {
 
         if quality == ShiftQuality::Perfect {
             self.perfect_shifts += 1;
-            self.perfect_shift_boost = 0.5;
+            self.perfect_shift_boost = PERFECT_SHIFT_BOOST_TIME;
         }
 
+        // Road speed is unchanged, so the revs drop by the step between the two ratios
+        let old_ratio = car.gear_ratios[self.gear as usize];
         self.gear += 1;
-        self.rpm = (self.rpm as f64 * 0.65) as u32;
+        let new_ratio = car.gear_ratios[self.gear as usize];
+        self.rpm = (self.rpm as f64 * new_ratio / old_ratio) as u32;
+        // Revs held by the launch don't carry into the next gear
+        self.launch_control = false;
+        self.clutch_rpm = 0;
+        self.shift_timer = if car.transmission == Transmission::Manual && !with_clutch {
+            car.shift_duration() + FORCED_SHIFT_PENALTY
+        } else {
+            car.shift_duration()
+        };
     }
 }
 
+/// How a perfect driver runs a car on the day
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerfectRun {
+    /// RPM it changes up at, one per upshift
+    pub shift_rpms: Vec<u32>,
+    /// Its quarter mile ET, unless the car can't make it down the track
+    pub et: Option<f64>,
+}
+
 pub struct RaceState {
     pub player: RaceCarState,
     pub opponent: RaceCarState,
     pub player_car: Car,
     pub opponent_car: Car,
     pub christmas_tree: ChristmasTree,
+    pub settings: RaceSettings,
     pub race_started: bool,
     pub race_finished: bool,
     pub winner: Option<Winner>,
-    pub elapsed_time: f64,
     pub ai: AI,
-    green_light_time: Option<Instant>,
+    // Recorded run driving the opponent lane instead of the AI
+    pub ghost: Option<Ghost>,
+    // Seed for any randomness in the race, so replays reproduce it exactly
+    pub seed: u64,
+    pub opponent_tree: ChristmasTree,
+    // Simulation clock, advanced only by `update` so runs are reproducible
+    sim_time: f64,
+    // Where the player's pedal is heading, and how far the driver allows it to open
+    player_throttle_target: f64,
+    player_throttle_limit: f64,
+    // Launch control held, so the throttle builds revs instead of launching
+    player_launch_control: bool,
 }
 
 impl RaceState {
-    pub fn new(player_car: Car, opponent_car: Car) -> Self {
+    /// Race against the AI. In a bracket race the AI picks its own dial-in, so the
+    /// opponent dial in `settings` is only kept if the AI's planned run never finishes.
+    /// A perfect AI changes up at `perfect_shift_rpms`, from `perfect_run` for
+    /// `opponent_car`; other difficulties ignore them.
+    pub fn new(
+        player_car: Car,
+        opponent_car: Car,
+        settings: RaceSettings,
+        seed: u64,
+        perfect_shift_rpms: &[u32],
+    ) -> Self {
+        Self::with_opponent(
+            player_car,
+            opponent_car,
+            settings,
+            seed,
+            perfect_shift_rpms,
+            None,
+        )
+    }
+
+    /// Race against a recorded run of `ghost.car` instead of an AI opponent
+    pub fn new_ghost_race(
+        player_car: Car,
+        ghost: Ghost,
+        settings: RaceSettings,
+        seed: u64,
+    ) -> Self {
+        Self::with_opponent(
+            player_car,
+            ghost.car.clone(),
+            settings,
+            seed,
+            &[],
+            Some(ghost),
+        )
+    }
+
+    fn with_opponent(
+        player_car: Car,
+        opponent_car: Car,
+        mut settings: RaceSettings,
+        seed: u64,
+        perfect_shift_rpms: &[u32],
+        ghost: Option<Ghost>,
+    ) -> Self {
+        // Both lanes share the random staging hold so a bracket stagger stays exact
+        let mut rng = StdRng::seed_from_u64(seed);
+        let staging_delay = rng.random_range(0.0..MAX_STAGING_DELAY);
+        let personality = Personality::random(&mut rng);
+        let mut ai = if settings.difficulty == AiDifficulty::Perfect {
+            AI::perfect_driver(perfect_shift_rpms.to_vec())
+        } else {
+            AI::new(settings.difficulty, personality)
+        };
+        // The AI dials in off a clean run in its car, before it knows how today's race
+        // will go, and misjudges it by as much as its skill allows. Its mistakes can
+        // still cost it, and a run that beats its guess breaks out.
+        let clean_et = match settings.mode {
+            RaceMode::Bracket { .. } if ghost.is_none() => {
+                Self::simulate_ai_run(ai.clone(), &opponent_car, &settings.conditions)
+            }
+            _ => None,
+        };
+        ai.plan_race(&mut rng, &opponent_car);
+        if let Some(et) = clean_et
+            && let RaceMode::Bracket { opponent_dial, .. } = &mut settings.mode
+        {
+            let dial = et + ai.dial_in_error(&mut rng) - AI_DIAL_IN_MARGIN;
+            *opponent_dial = (dial * 100.0).floor() / 100.0;
+        }
+        let (player_delay, opponent_delay) = settings.mode.start_delays();
+
         Self {
             player: RaceCarState::new(),
             opponent: RaceCarState::new(),
             player_car,
             opponent_car,
-            christmas_tree: ChristmasTree::new(),
+            christmas_tree: ChristmasTree::new(settings.tree, staging_delay + player_delay),
+            settings,
             race_started: false,
             race_finished: false,
             winner: None,
-            elapsed_time: 0.0,
-            ai: AI::new(0.15), // Medium difficulty
-            green_light_time: None,
+            ai,
+            ghost,
+            seed,
+            opponent_tree: ChristmasTree::new(settings.tree, staging_delay + opponent_delay),
+            sim_time: 0.0,
+            player_throttle_target: 0.0,
+            player_throttle_limit: 1.0,
+            player_launch_control: false,
         }
     }
 
@@ -162,47 +471,262 @@ impl RaceState {
             return;
         }
 
-        // Update countdown
-        if !self.race_started {
-            if self.christmas_tree.update(delta_time) {
-                self.green_light_time = Some(Instant::now());
+        // Launch at the start of the step, matching a player launch between steps
+        self.launch_opponent();
+
+        self.sim_time += delta_time;
+
+        // Update countdowns, each lane has its own so bracket starts can be staggered
+        self.christmas_tree.update(delta_time);
+        self.opponent_tree.update(delta_time);
+
+        // Update player, free-revving in neutral until they launch
+        self.move_player_throttle(delta_time);
+        if self.player.launch_time.is_some() {
+            let player_prev_position = self.player.position;
+            let player_car = self.player_car.clone();
+            Self::update_car_static(
+                &player_car,
+                &self.settings.conditions,
+                &mut self.player,
+                delta_time,
+            );
+            Self::record_progress(&mut self.player, player_prev_position, delta_time);
+        } else {
+            let rev_limit = if self.player_launch_control {
+                self.player_car.two_step_limit()
+            } else {
+                self.player_car.redline
+            };
+            Self::rev_engine(&mut self.player, rev_limit, delta_time);
+        }
+
+        // Update opponent from the ghost recording, or with AI
+        let opponent_prev_position = self.opponent.position;
+        if let Some(ghost) = &self.ghost {
+            if self.opponent.launch_time.is_some() {
+                let green = self.opponent_tree.green_time();
+                ghost.apply(self.sim_time - green, &mut self.opponent);
             }
+        } else if self.opponent.launch_time.is_some() {
+            let opponent_car = self.opponent_car.clone();
+            self.ai
+                .update(&mut self.opponent, &opponent_car, delta_time);
+            Self::update_car_static(
+                &opponent_car,
+                &self.settings.conditions,
+                &mut self.opponent,
+                delta_time,
+            );
+        }
+        if self.opponent.launch_time.is_some() {
+            Self::record_progress(&mut self.opponent, opponent_prev_position, delta_time);
+        }
+
+        // The race is over once every lane has an outcome
+        if let (Some(_), Some(_)) = self.outcomes() {
+            self.race_finished = true;
+            self.winner = self.decide_winner();
+        }
+    }
+
+    fn launch_opponent(&mut self) {
+        if self.opponent.launch_time.is_some() {
+            return;
+        }
+        // A ghost leaves with its recorded reaction, the AI with its own, which may
+        // be before green
+        let launch_delay = match &self.ghost {
+            Some(ghost) => ghost.launch_delay,
+            None => self.ai.reaction_time(),
+        };
+        let green = self.opponent_tree.green_time();
+        if !self.opponent_tree.is_running() || self.sim_time - green < launch_delay {
             return;
         }
 
-        self.elapsed_time += delta_time;
+        let perfect = self.settings.tree.perfect_reaction();
+        if self.ghost.is_some() {
+            // The ghost's motion is replayed from its own clock, so start its run
+            // clock at the exact recorded launch rather than this step
+            self.opponent.reaction_time = Some(perfect + launch_delay);
+            self.opponent.launch_time = Some(green + launch_delay);
+            self.opponent.run_time = self.sim_time - (green + launch_delay);
+        } else {
+            self.ai.start_race(&mut self.opponent, &self.opponent_car);
+            self.opponent.reaction_time = Some(perfect + self.sim_time - green);
+            self.opponent.red_light = self.sim_time < green;
+            self.opponent.launch_time = Some(self.sim_time);
+        }
+        self.opponent_tree.state = LightState::Racing;
+    }
 
-        // Update player
-        let player_car = self.player_car.clone();
-        Self::update_car_static(&player_car, &mut self.player, delta_time);
+    // Advance a launched lane's clock and record its splits and finish
+    fn record_progress(state: &mut RaceCarState, prev_position: f64, delta_time: f64) {
+        if state.finish_time.is_some() {
+            return;
+        }
 
-        // Update opponent with AI
-        let opponent_car = self.opponent_car.clone();
-        self.ai
-            .update(&mut self.opponent, &opponent_car, delta_time);
-        Self::update_car_static(&opponent_car, &mut self.opponent, delta_time);
+        let prev_time = state.run_time;
+        state.run_time += delta_time;
+        state
+            .slip
+            .record(prev_position, state.position, prev_time, state.run_time);
 
-        // Check for finish
-        if self.player.position >= FINISH_LINE && self.player.finish_time.is_none() {
-            self.player.finish_time = Some(self.elapsed_time);
-            if self.winner.is_none() {
-                self.winner = Some(Winner::Player);
-            }
+        // Use the interpolated crossing time as the ET
+        if state.position >= FINISH_LINE {
+            state.finish_time = state.slip.quarter_mile.or(Some(state.run_time));
         }
+    }
+
+    fn decide_winner(&self) -> Option<Winner> {
+        use LaneOutcome::*;
+
+        let (player, opponent) = self.outcomes();
+        let winner = match (player?, opponent?) {
+            // Red lights are judged first. Both lanes share a tree type, so when both
+            // fouled the lower reaction time left earlier and loses (first or worse).
+            (RedLight, RedLight) => {
+                if self.player.reaction_time? < self.opponent.reaction_time? {
+                    Winner::Opponent
+                } else {
+                    Winner::Player
+                }
+            }
+            (RedLight, _) => Winner::Opponent,
+            (_, RedLight) => Winner::Player,
 
-        if self.opponent.position >= FINISH_LINE && self.opponent.finish_time.is_none() {
-            self.opponent.finish_time = Some(self.elapsed_time);
-            if self.winner.is_none() {
-                self.winner = Some(Winner::Opponent);
+            // A blown engine loses to any finisher. If both blew, the car that got further wins.
+            (DnfBlown, DnfBlown) => {
+                if self.opponent.position > self.player.position {
+                    Winner::Opponent
+                } else {
+                    Winner::Player
+                }
+            }
+            (DnfBlown, _) => Winner::Opponent,
+            (_, DnfBlown) => Winner::Player,
+
+            // Double breakout: whoever broke out by less wins
+            (Breakout, Breakout) => {
+                let (player_breakout, opponent_breakout) = self.breakouts();
+                if player_breakout? <= opponent_breakout? {
+                    Winner::Player
+                } else {
+                    Winner::Opponent
+                }
+            }
+            (Breakout, Finished) => Winner::Opponent,
+            (Finished, Breakout) => Winner::Player,
+
+            // First to the finish line wins, so reaction time counts too
+            (Finished, Finished) => {
+                let player_crossing = self.player.launch_time? + self.player.finish_time?;
+                let opponent_crossing = self.opponent.launch_time? + self.opponent.finish_time?;
+                if opponent_crossing < player_crossing {
+                    Winner::Opponent
+                } else {
+                    Winner::Player
+                }
             }
+        };
+        Some(winner)
+    }
+
+    /// How each lane's run ended, as (player, opponent). `None` for a lane that is
+    /// still running or hasn't left yet.
+    pub fn outcomes(&self) -> (Option<LaneOutcome>, Option<LaneOutcome>) {
+        let (player_dial, opponent_dial) = match self.settings.mode {
+            RaceMode::HeadsUp => (None, None),
+            RaceMode::Bracket {
+                player_dial,
+                opponent_dial,
+            } => (Some(player_dial), Some(opponent_dial)),
+        };
+        (
+            Self::lane_outcome(&self.player, player_dial),
+            Self::lane_outcome(&self.opponent, opponent_dial),
+        )
+    }
+
+    // A red light settles a lane the moment it leaves, whatever happens after
+    fn lane_outcome(lane: &RaceCarState, dial: Option<f64>) -> Option<LaneOutcome> {
+        if lane.red_light {
+            return Some(LaneOutcome::RedLight);
         }
+        if lane.blown_engine {
+            return Some(LaneOutcome::DnfBlown);
+        }
+        let et = lane.finish_time?;
+        Some(if dial.is_some_and(|dial| et < dial) {
+            LaneOutcome::Breakout
+        } else {
+            LaneOutcome::Finished
+        })
+    }
 
-        if self.player.finish_time.is_some() && self.opponent.finish_time.is_some() {
-            self.race_finished = true;
+    /// How far each lane ran under its dial-in, as (player, opponent). `None` for a lane
+    /// that didn't break out or outside bracket races.
+    pub fn breakouts(&self) -> (Option<f64>, Option<f64>) {
+        let RaceMode::Bracket {
+            player_dial,
+            opponent_dial,
+        } = self.settings.mode
+        else {
+            return (None, None);
+        };
+
+        let breakout = |et: Option<f64>, dial: f64| et.filter(|et| *et < dial).map(|et| dial - et);
+        (
+            breakout(self.player.finish_time, player_dial),
+            breakout(self.opponent.finish_time, opponent_dial),
+        )
+    }
+
+    /// Gap between the two cars crossing the finish line, including reaction times
+    pub fn finish_margin(&self) -> Option<f64> {
+        let player_crossing = self.player.launch_time? + self.player.finish_time?;
+        let opponent_crossing = self.opponent.launch_time? + self.opponent.finish_time?;
+        Some((player_crossing - opponent_crossing).abs())
+    }
+
+    /// Quarter mile ET the AI would run in `car` in `conditions`, used to suggest dial-ins
+    pub fn estimate_et(car: &Car, conditions: &TrackConditions) -> Option<f64> {
+        // Without a planned race the AI shifts right on its shift point
+        let ai = AI::new(AiDifficulty::default(), Personality::default());
+        Self::simulate_ai_run(ai, car, conditions)
+    }
+
+    /// The race a perfect driver runs in `car` in `conditions`. Its ET is the benchmark
+    /// the player measures their runs against.
+    pub fn perfect_run(car: &Car, conditions: &TrackConditions) -> PerfectRun {
+        let shift_rpms = calculate_optimal_shift_rpms(car, conditions);
+        let et = Self::simulate_ai_run(AI::perfect_driver(shift_rpms.clone()), car, conditions);
+        PerfectRun { shift_rpms, et }
+    }
+
+    // Quarter mile ET `ai` runs in `car` with nobody in the other lane
+    fn simulate_ai_run(mut ai: AI, car: &Car, conditions: &TrackConditions) -> Option<f64> {
+        const STEP: f64 = 0.001;
+
+        let mut state = RaceCarState::new();
+        ai.start_race(&mut state, car);
+
+        while state.finish_time.is_none() && !state.blown_engine && state.run_time < 120.0 {
+            let prev_position = state.position;
+            ai.update(&mut state, car, STEP);
+            Self::update_car_static(car, conditions, &mut state, STEP);
+            Self::record_progress(&mut state, prev_position, STEP);
         }
+        state.finish_time
     }
 
-    fn update_car_static(car: &Car, state: &mut RaceCarState, delta_time: f64) {
+    fn update_car_static(
+        car: &Car,
+        conditions: &TrackConditions,
+        state: &mut RaceCarState,
+        delta_time: f64,
+    ) {
         if state.blown_engine || state.finish_time.is_some() {
             return;
         }
@@ -213,45 +737,93 @@ impl RaceState {
             state.perfect_shift_boost = state.perfect_shift_boost.max(0.0);
         }
 
-        // Calculate acceleration
-        let acceleration = calculate_acceleration(
+        // Calculate acceleration, limited by what the tires can hold. Nothing reaches
+        // the wheels with the clutch in or mid-shift.
+        let mut drive_force = if state.clutch_in || state.shift_timer > 0.0 {
+            0.0
+        } else {
+            let turbine_rpm = calculate_rpm(car, state.wheel_speed, state.gear);
+            calculate_drive_force(
+                car,
+                conditions,
+                state.rpm,
+                state.gear,
+                state.throttle,
+                state.nos_active,
+                state.perfect_shift_boost,
+            ) * calculate_converter_torque_ratio(car, turbine_rpm, state.rpm)
+        };
+        state.shift_timer = (state.shift_timer - delta_time).max(0.0);
+        if state.launch_control {
+            let grip = calculate_traction(car, conditions, 0.0, state.velocity, state.acceleration);
+            drive_force = drive_force.min(grip);
+        }
+        let slip_ratio = calculate_slip_ratio(state.wheel_speed, state.velocity);
+        let traction = calculate_traction(
             car,
+            conditions,
+            slip_ratio,
             state.velocity,
-            state.rpm,
-            state.gear,
-            state.throttle,
-            state.nos_active,
-            state.perfect_shift_boost,
+            state.acceleration,
         );
+        let acceleration =
+            calculate_acceleration(car, conditions, state.velocity, drive_force, traction);
+        state.acceleration = acceleration;
 
-        // Update velocity and position
+        // Update velocity, then advance position by the average velocity over the step
+        let start_velocity = state.velocity;
         state.velocity += acceleration * delta_time;
         state.velocity = state.velocity.max(0.0);
-        state.position += state.velocity * delta_time;
+        state.position += (start_velocity + state.velocity) * 0.5 * delta_time;
+
+        // Force the tires can't hold spins the wheels up; once it drops below the
+        // traction they hook back up to the car's speed
+        state.wheel_speed += calculate_wheel_acceleration(drive_force, traction) * delta_time;
+        state.wheel_speed = state.wheel_speed.max(state.velocity);
 
         // Track top speed
         if state.velocity > state.top_speed {
             state.top_speed = state.velocity;
         }
 
-        // Update RPM based on velocity and gear
-        if state.gear < car.gear_ratios.len() as u8 {
-            let gear_ratio = car.gear_ratios[state.gear as usize];
-            state.rpm = calculate_rpm(state.velocity, gear_ratio);
+        // Update RPM based on wheel speed and gear, so wheelspin flares the revs.
+        // Revs held by the clutch only fade as it hooks up, launch control holds them
+        // until the wheels catch up, and with the clutch in the engine revs freely.
+        let wheel_rpm = calculate_rpm(car, state.wheel_speed, state.gear);
+        if wheel_rpm >= state.clutch_rpm {
+            state.launch_control = false;
+        }
+        if !state.launch_control {
+            state.clutch_rpm = state
+                .clutch_rpm
+                .saturating_sub((car.redline as f64 / CLUTCH_TAKE_UP_TIME * delta_time) as u32);
+        }
+        if state.clutch_in {
+            Self::rev_engine(state, car.redline, delta_time);
+        } else if car.transmission == Transmission::Automatic {
+            state.rpm = calculate_converter_rpm(car, wheel_rpm, state.throttle);
+        } else {
+            state.rpm = wheel_rpm.max(state.clutch_rpm);
         }
 
         // Engine heat management
-        if state.rpm > (car.redline as f64 * 0.9) as u32 {
-            state.engine_heat += delta_time * 0.3;
+        let mut heat_rate = if state.rpm > (car.redline as f64 * OVER_REV_FRACTION) as u32 {
+            OVER_REV_HEAT_RATE
         } else {
-            state.engine_heat -= delta_time * 0.1;
+            -COOLING_RATE
+        };
+        if state.nos_active {
+            heat_rate += NOS_HEAT_RATE;
         }
+        state.engine_heat += heat_rate * delta_time;
         state.engine_heat = state.engine_heat.clamp(0.0, 1.0);
 
         // Blown engine
         if state.engine_heat >= 1.0 {
             state.blown_engine = true;
             state.velocity = 0.0;
+            state.wheel_speed = 0.0;
+            state.acceleration = 0.0;
         }
 
         // NOS management
@@ -263,61 +835,132 @@ impl RaceState {
             }
         }
 
+        // Automatics change up on their own
+        if car.transmission == Transmission::Automatic
+            && state.rpm >= (car.redline as f64 * AUTOMATIC_UPSHIFT_FRACTION) as u32
+        {
+            state.shift_up(car, true);
+        }
+
         // Auto-shift if over redline (safety)
         if state.rpm > car.redline && state.gear < car.gear_ratios.len() as u8 - 1 {
-            state.shift_up(car);
+            state.shift_up(car, false);
         }
     }
 
+    // Free-rev an engine that isn't driving the wheels, up to `rev_limit`
+    fn rev_engine(state: &mut RaceCarState, rev_limit: u32, delta_time: f64) {
+        let idle = IDLE_RPM as f64;
+        let limit = rev_limit as f64;
+        let target = (idle + state.throttle * (limit - idle)).min(limit);
+        let rpm = state.rpm as f64;
+        let step = FREE_REV_RATE * delta_time;
+        state.rpm = if rpm < target {
+            (rpm + step).min(target)
+        } else {
+            (rpm - step).max(target)
+        } as u32;
+    }
+
     pub fn player_throttle(&mut self) {
         if !self.race_started {
-            match self.christmas_tree.state {
-                LightState::Green => {
-                    // Calculate reaction time
-                    if let Some(green_time) = self.green_light_time {
-                        let reaction = green_time.elapsed().as_secs_f64();
-                        self.player.reaction_time = Some(reaction);
-                        self.race_started = true;
-
-                        // Transition to Racing state
-                        self.christmas_tree.state = LightState::Racing;
-
-                        // Start the AI opponent
-                        self.ai.start_race(&mut self.opponent);
-                    }
-                }
-                LightState::Yellow1 | LightState::Yellow2 | LightState::Yellow3 => {
-                    // Red light - jumped the start during yellow lights!
-                    self.player.reaction_time = Some(-1.0); // Negative indicates red light
-                    self.race_started = true;
-                    self.race_finished = true;
-                    self.winner = Some(Winner::Opponent);
-
-                    // Transition to Racing state to show red light
-                    self.christmas_tree.state = LightState::Racing;
-                    return;
-                }
-                _ => {
-                    // Pre-stage or staged - no penalty, just ignore
-                    return;
-                }
+            // Launch control or the clutch holds the car, so the throttle only builds revs
+            if self.player_launch_control || self.player.clutch_in {
+                self.player_throttle_target = self.player_throttle_limit;
+                return;
             }
-        }
 
-        if self.race_started {
-            self.player.throttle = 1.0;
+            // Pre-stage or staged - no penalty, just ignore, and the engine drops
+            // back to idle rather than revving against nothing
+            if !self.christmas_tree.is_running() {
+                self.player_throttle_target = 0.0;
+                self.player.launch_control = false;
+                return;
+            }
+
+            // Reaction time is measured from the last amber, so leaving on the ambers
+            // is a red light. The car still makes its run.
+            let green_time = self.christmas_tree.green_time();
+            let reaction = self.settings.tree.perfect_reaction() + self.sim_time - green_time;
+            self.player.reaction_time = Some(reaction);
+            self.player.red_light = self.sim_time < green_time;
+            self.player.launch_time = Some(self.sim_time);
+            self.player.clutch_rpm = self.player.rpm;
+            self.race_started = true;
+
+            // Transition to Racing state
+            self.christmas_tree.state = LightState::Racing;
         }
+
+        self.player_throttle_target = self.player_throttle_limit;
     }
 
     pub fn player_release_throttle(&mut self) {
-        if self.race_started {
-            self.player.throttle = 0.0;
+        self.player_throttle_target = 0.0;
+    }
+
+    pub fn player_press_clutch(&mut self) {
+        if self.player_car.transmission.has_clutch_pedal() {
+            self.player.clutch_in = true;
+        }
+    }
+
+    /// Let the clutch out; before launch, doing this with the throttle down launches
+    pub fn player_release_clutch(&mut self) {
+        self.player.release_clutch();
+    }
+
+    /// Hold the car on launch control; releasing it with the throttle down launches
+    pub fn player_engage_launch_control(&mut self) {
+        if !self.race_started {
+            self.player_launch_control = true;
+        }
+    }
+
+    pub fn player_release_launch_control(&mut self) {
+        // Leaving the line as it's let go launches on launch control
+        if self.player_launch_control && !self.race_started && self.player_throttle_target > 0.0 {
+            self.player.launch_control = true;
+        }
+        self.player_launch_control = false;
+    }
+
+    /// Whether the player's engine is bouncing off the two-step limiter
+    pub fn player_on_two_step(&self) -> bool {
+        !self.race_started
+            && self.player_launch_control
+            && self.player.rpm >= self.player_car.two_step_limit()
+    }
+
+    /// Cap how far the pedal opens while the throttle is held, from 0.0 to 1.0
+    pub fn player_set_throttle_limit(&mut self, limit: f64) {
+        self.player_throttle_limit = limit.clamp(0.0, 1.0);
+        if self.player_throttle_target > 0.0 {
+            self.player_throttle_target = self.player_throttle_limit;
+        }
+    }
+
+    pub fn player_throttle_limit(&self) -> f64 {
+        self.player_throttle_limit
+    }
+
+    // Ease the pedal towards its target at the chosen throttle response
+    fn move_player_throttle(&mut self, delta_time: f64) {
+        let (rise, fall) = self.settings.throttle.rates();
+        let target = self.player_throttle_target;
+        let throttle = &mut self.player.throttle;
+        if *throttle < target {
+            *throttle = (*throttle + rise * delta_time).min(target);
+        } else {
+            *throttle = (*throttle - fall * delta_time).max(target);
         }
     }
 
     pub fn player_shift_up(&mut self) {
-        if self.race_started {
-            self.player.shift_up(&self.player_car.clone());
+        // An automatic picks its own gears
+        if self.race_started && self.player_car.transmission != Transmission::Automatic {
+            let with_clutch = self.player.clutch_in;
+            self.player.shift_up(&self.player_car.clone(), with_clutch);
         }
     }
 
@@ -333,6 +976,22 @@ impl RaceState {
         }
     }
 
+    /// Total simulated time since the race was created, including the countdown
+    pub fn sim_time(&self) -> f64 {
+        self.sim_time
+    }
+
+    /// Simulated time since the green light, or `None` before it has come on
+    pub fn time_since_green(&self) -> Option<f64> {
+        let time = self.sim_time - self.christmas_tree.green_time();
+        (time >= 0.0).then_some(time)
+    }
+
+    /// Seconds between the player's green light and their launch
+    pub fn player_launch_delay(&self) -> Option<f64> {
+        Some(self.player.launch_time? - self.christmas_tree.green_time())
+    }
+
     pub fn is_finished(&self) -> bool {
         self.race_finished
     }
@@ -345,3 +1004,328 @@ impl RaceState {
         (self.opponent.position / FINISH_LINE).min(1.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::CarCatalog;
+
+    const STEP: f64 = 0.001;
+
+    fn civic() -> Car {
+        CarCatalog::bundled().cars.remove(0)
+    }
+
+    /// Drive the player down the track, leaving `reaction` seconds after green and
+    /// shifting at 90% of redline, until both lanes are settled
+    fn run_race(race: &mut RaceState, reaction: f64) {
+        while !race.is_finished() && race.sim_time() < 60.0 {
+            if race.time_since_green().is_some_and(|time| time >= reaction) {
+                race.player_throttle();
+                if race.player.rpm as f64 >= race.player_car.redline as f64 * 0.9 {
+                    race.player_shift_up();
+                }
+            }
+            race.update(STEP);
+        }
+    }
+
+    /// A lane that launched at `launch_time` on the simulation clock and ran `et`
+    fn finished_lane(launch_time: f64, et: f64) -> RaceCarState {
+        let mut lane = RaceCarState::new();
+        lane.launch_time = Some(launch_time);
+        lane.finish_time = Some(et);
+        lane
+    }
+
+    fn bracket(player_dial: f64, opponent_dial: f64) -> RaceMode {
+        RaceMode::Bracket {
+            player_dial,
+            opponent_dial,
+        }
+    }
+
+    enum Start {
+        Plain,
+        LaunchControl,
+        /// Clutch held and the engine revved on this share of the throttle
+        ClutchDump(f64),
+    }
+
+    /// Sixty-foot time of a start on the green, full throttle once moving and shifting
+    /// at 90% of redline on the tach
+    fn sixty_foot(car: &Car, start: Start) -> f64 {
+        let mut race = RaceState::new(car.clone(), car.clone(), RaceSettings::default(), 1, &[]);
+        while race.time_since_green().is_none() {
+            match start {
+                Start::Plain => {}
+                Start::LaunchControl => race.player_engage_launch_control(),
+                Start::ClutchDump(throttle) => {
+                    race.player_set_throttle_limit(throttle);
+                    race.player_press_clutch();
+                }
+            }
+            if !matches!(start, Start::Plain) {
+                race.player_throttle();
+            }
+            race.update(STEP);
+        }
+        race.player_release_launch_control();
+        race.player_release_clutch();
+        race.player_set_throttle_limit(1.0);
+        while race.player.slip.sixty_foot.is_none() {
+            race.player_throttle();
+            if race.player.rpm as f64 >= car.redline as f64 * 0.9 {
+                race.player_shift_up();
+            }
+            race.update(STEP);
+        }
+        race.player.slip.sixty_foot.unwrap()
+    }
+
+    #[test]
+    fn launch_control_beats_a_plain_launch_to_sixty_feet() {
+        for car in CarCatalog::bundled().cars {
+            let launched = sixty_foot(&car, Start::LaunchControl);
+            let plain = sixty_foot(&car, Start::Plain);
+            assert!(launched < plain, "{}: {launched} vs {plain}", car.name);
+        }
+    }
+
+    #[test]
+    fn a_well_judged_clutch_dump_beats_a_plain_launch_but_not_launch_control() {
+        let clutch_cars = CarCatalog::bundled()
+            .cars
+            .into_iter()
+            .filter(|car| car.transmission.has_clutch_pedal());
+        for car in clutch_cars {
+            let plain = sixty_foot(&car, Start::Plain);
+            let launch_control = sixty_foot(&car, Start::LaunchControl);
+            let dumps: Vec<f64> = (1..=10)
+                .map(|step| sixty_foot(&car, Start::ClutchDump(step as f64 / 10.0)))
+                .collect();
+
+            let best = dumps.iter().copied().fold(f64::INFINITY, f64::min);
+            assert!(best < plain, "{}: {best} vs {plain}", car.name);
+            assert!(
+                best >= launch_control,
+                "{}: {best} vs {launch_control}",
+                car.name
+            );
+        }
+    }
+
+    #[test]
+    fn reaction_time_runs_on_the_simulation_clock() {
+        let mut race = RaceState::new(civic(), civic(), RaceSettings::default(), 1, &[]);
+        while race.time_since_green().is_none_or(|time| time < 0.2) {
+            race.update(STEP);
+        }
+        let since_green = race.time_since_green().unwrap();
+        race.player_throttle();
+
+        let perfect = race.settings.tree.perfect_reaction();
+        let reaction = race.player.reaction_time.unwrap();
+        assert!((reaction - (perfect + since_green)).abs() < 1e-9);
+        assert!((since_green - 0.2).abs() <= STEP);
+        assert!(!race.player.red_light);
+    }
+
+    #[test]
+    fn the_same_inputs_replay_the_same_race() {
+        let settings = RaceSettings {
+            difficulty: AiDifficulty::Medium,
+            ..RaceSettings::default()
+        };
+        let mut first = RaceState::new(civic(), civic(), settings, 7, &[]);
+        let mut second = RaceState::new(civic(), civic(), settings, 7, &[]);
+        run_race(&mut first, 0.15);
+        run_race(&mut second, 0.15);
+
+        assert!(first.is_finished());
+        assert_eq!(first.player.reaction_time, second.player.reaction_time);
+        assert_eq!(first.player.finish_time, second.player.finish_time);
+        assert_eq!(first.opponent.reaction_time, second.opponent.reaction_time);
+        assert_eq!(first.opponent.finish_time, second.opponent.finish_time);
+        assert_eq!(first.winner, second.winner);
+    }
+
+    #[test]
+    fn the_quicker_dial_in_waits_out_the_difference_on_the_tree() {
+        let settings = RaceSettings {
+            mode: bracket(16.0, 15.0),
+            ..RaceSettings::default()
+        };
+        let race = RaceState::new(civic(), civic(), settings, 1, &[]);
+        let RaceMode::Bracket {
+            player_dial,
+            opponent_dial,
+        } = race.settings.mode
+        else {
+            panic!("not a bracket race");
+        };
+
+        let stagger = race.christmas_tree.green_time() - race.opponent_tree.green_time();
+        assert!((stagger - (opponent_dial - player_dial)).abs() < 1e-9);
+        assert_eq!(bracket(12.0, 13.5).start_delays(), (1.5, 0.0));
+        assert_eq!(bracket(13.5, 12.0).start_delays(), (0.0, 1.5));
+        assert_eq!(RaceMode::HeadsUp.start_delays(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn running_under_the_dial_in_loses_even_first_to_the_line() {
+        let mut race = RaceState::new(civic(), civic(), RaceSettings::default(), 1, &[]);
+        race.settings.mode = bracket(12.0, 12.4);
+        race.player = finished_lane(2.0, 11.9);
+        race.opponent = finished_lane(2.0, 12.5);
+
+        assert_eq!(
+            race.outcomes(),
+            (Some(LaneOutcome::Breakout), Some(LaneOutcome::Finished))
+        );
+        let (player_breakout, opponent_breakout) = race.breakouts();
+        assert!((player_breakout.unwrap() - 0.1).abs() < 1e-9);
+        assert_eq!(opponent_breakout, None);
+        assert_eq!(race.decide_winner(), Some(Winner::Opponent));
+    }
+
+    #[test]
+    fn a_double_breakout_goes_to_the_smaller_breakout() {
+        let mut race = RaceState::new(civic(), civic(), RaceSettings::default(), 1, &[]);
+        race.settings.mode = bracket(12.0, 12.0);
+        race.player = finished_lane(2.0, 11.95);
+        race.opponent = finished_lane(2.5, 11.98);
+
+        assert_eq!(
+            race.outcomes(),
+            (Some(LaneOutcome::Breakout), Some(LaneOutcome::Breakout))
+        );
+        assert_eq!(race.decide_winner(), Some(Winner::Opponent));
+
+        race.opponent = finished_lane(2.5, 11.9);
+        assert_eq!(race.decide_winner(), Some(Winner::Player));
+    }
+
+    #[test]
+    fn leaving_before_green_is_a_red_light() {
+        let mut race = RaceState::new(civic(), civic(), RaceSettings::default(), 1, &[]);
+        while !race.christmas_tree.is_running() {
+            race.update(STEP);
+        }
+        race.player_throttle();
+
+        let perfect = race.settings.tree.perfect_reaction();
+        assert!(race.player.red_light);
+        assert!(race.player.reaction_time.unwrap() < perfect);
+        assert_eq!(race.outcomes().0, Some(LaneOutcome::RedLight));
+    }
+
+    #[test]
+    fn the_ai_can_red_light_too() {
+        let settings = RaceSettings {
+            difficulty: AiDifficulty::Pro,
+            ..RaceSettings::default()
+        };
+        let mut race = (0..)
+            .map(|seed| RaceState::new(civic(), civic(), settings, seed, &[]))
+            .find(|race| race.ai.reaction_time() < 0.0)
+            .unwrap();
+        while race.opponent.launch_time.is_none() {
+            race.update(STEP);
+        }
+
+        assert!(race.opponent.red_light);
+        assert!(race.time_since_green().is_none());
+        assert_eq!(race.outcomes().1, Some(LaneOutcome::RedLight));
+    }
+
+    #[test]
+    fn a_red_light_loses_to_anything_and_the_first_foul_loses_a_double() {
+        let mut race = RaceState::new(civic(), civic(), RaceSettings::default(), 1, &[]);
+        race.player = finished_lane(2.0, 11.0);
+        race.player.red_light = true;
+        race.player.reaction_time = Some(0.45);
+        race.opponent = finished_lane(2.5, 15.0);
+        race.opponent.blown_engine = true;
+        assert_eq!(race.decide_winner(), Some(Winner::Opponent));
+
+        // Both fouled, so the one who left earlier loses
+        race.opponent.red_light = true;
+        race.opponent.reaction_time = Some(0.4);
+        assert_eq!(race.decide_winner(), Some(Winner::Player));
+        race.opponent.reaction_time = Some(0.48);
+        assert_eq!(race.decide_winner(), Some(Winner::Opponent));
+    }
+
+    #[test]
+    fn a_lane_is_settled_once_it_finishes_blows_up_or_fouls() {
+        let mut lane = RaceCarState::new();
+        assert_eq!(RaceState::lane_outcome(&lane, None), None);
+
+        lane.blown_engine = true;
+        assert_eq!(
+            RaceState::lane_outcome(&lane, None),
+            Some(LaneOutcome::DnfBlown)
+        );
+        lane.red_light = true;
+        assert_eq!(
+            RaceState::lane_outcome(&lane, None),
+            Some(LaneOutcome::RedLight)
+        );
+
+        let lane = finished_lane(2.0, 12.0);
+        assert_eq!(
+            RaceState::lane_outcome(&lane, None),
+            Some(LaneOutcome::Finished)
+        );
+        assert_eq!(
+            RaceState::lane_outcome(&lane, Some(12.0)),
+            Some(LaneOutcome::Finished)
+        );
+        assert_eq!(
+            RaceState::lane_outcome(&lane, Some(12.1)),
+            Some(LaneOutcome::Breakout)
+        );
+    }
+
+    #[test]
+    fn the_first_car_across_the_line_wins_a_heads_up_race() {
+        let mut race = RaceState::new(civic(), civic(), RaceSettings::default(), 1, &[]);
+        race.player = finished_lane(2.1, 12.0);
+        race.opponent = finished_lane(2.0, 12.05);
+        assert_eq!(race.decide_winner(), Some(Winner::Opponent));
+
+        race.player = finished_lane(2.0, 12.0);
+        assert_eq!(race.decide_winner(), Some(Winner::Player));
+    }
+
+    #[test]
+    fn a_blown_engine_loses_and_the_further_car_wins_a_double_dnf() {
+        let mut race = RaceState::new(civic(), civic(), RaceSettings::default(), 1, &[]);
+        race.player = RaceCarState::new();
+        race.player.blown_engine = true;
+        race.player.position = 300.0;
+        race.opponent = finished_lane(2.0, 16.0);
+        assert_eq!(
+            race.outcomes(),
+            (Some(LaneOutcome::DnfBlown), Some(LaneOutcome::Finished))
+        );
+        assert_eq!(race.decide_winner(), Some(Winner::Opponent));
+
+        race.opponent = RaceCarState::new();
+        race.opponent.blown_engine = true;
+        race.opponent.position = 200.0;
+        assert_eq!(race.decide_winner(), Some(Winner::Player));
+    }
+
+    #[test]
+    fn a_race_with_a_blown_engine_still_ends() {
+        let mut race = RaceState::new(civic(), civic(), RaceSettings::default(), 1, &[]);
+        race.player.blown_engine = true;
+        run_race(&mut race, 0.0);
+
+        assert!(race.is_finished());
+        assert_eq!(race.outcomes().0, Some(LaneOutcome::DnfBlown));
+        assert_eq!(race.winner, Some(Winner::Opponent));
+    }
+}
@@ -0,0 +1,138 @@
+const FEET_TO_METERS: f64 = 0.3048;
+
+const SIXTY_FOOT: f64 = 60.0 * FEET_TO_METERS;
+const THREE_THIRTY_FOOT: f64 = 330.0 * FEET_TO_METERS;
+const EIGHTH_MILE: f64 = 660.0 * FEET_TO_METERS;
+const THOUSAND_FOOT: f64 = 1000.0 * FEET_TO_METERS;
+const QUARTER_MILE: f64 = 1320.0 * FEET_TO_METERS;
+
+// Speed traps measure average speed over the last 66 feet before the marker
+const TRAP_LENGTH: f64 = 66.0 * FEET_TO_METERS;
+
+/// Incremental timing slip for one lane, with times in seconds and speeds in m/s
+#[derive(Debug, Clone, Default)]
+pub struct TimingSlip {
+    pub sixty_foot: Option<f64>,
+    pub three_thirty: Option<f64>,
+    pub eighth_mile: Option<f64>,
+    pub eighth_mile_speed: Option<f64>,
+    pub thousand_foot: Option<f64>,
+    pub quarter_mile: Option<f64>,
+    pub quarter_mile_speed: Option<f64>,
+    eighth_trap_start: Option<f64>,
+    quarter_trap_start: Option<f64>,
+}
+
+impl TimingSlip {
+    /// Record any markers crossed while moving from `prev_position` at `prev_time`
+    /// to `position` at `time`. Crossing times are interpolated within the step.
+    pub fn record(&mut self, prev_position: f64, position: f64, prev_time: f64, time: f64) {
+        if position <= prev_position {
+            return;
+        }
+
+        let crossing = |marker: f64| -> Option<f64> {
+            if prev_position < marker && position >= marker {
+                let t = (marker - prev_position) / (position - prev_position);
+                Some(prev_time + (time - prev_time) * t)
+            } else {
+                None
+            }
+        };
+
+        let markers: [(&mut Option<f64>, f64); 7] = [
+            (&mut self.sixty_foot, SIXTY_FOOT),
+            (&mut self.three_thirty, THREE_THIRTY_FOOT),
+            (&mut self.eighth_trap_start, EIGHTH_MILE - TRAP_LENGTH),
+            (&mut self.eighth_mile, EIGHTH_MILE),
+            (&mut self.thousand_foot, THOUSAND_FOOT),
+            (&mut self.quarter_trap_start, QUARTER_MILE - TRAP_LENGTH),
+            (&mut self.quarter_mile, QUARTER_MILE),
+        ];
+        for (split, marker) in markers {
+            if split.is_none() {
+                *split = crossing(marker);
+            }
+        }
+
+        if self.eighth_mile_speed.is_none()
+            && let (Some(start), Some(end)) = (self.eighth_trap_start, self.eighth_mile)
+        {
+            self.eighth_mile_speed = Some(TRAP_LENGTH / (end - start).max(f64::EPSILON));
+        }
+        if self.quarter_mile_speed.is_none()
+            && let (Some(start), Some(end)) = (self.quarter_trap_start, self.quarter_mile)
+        {
+            self.quarter_mile_speed = Some(TRAP_LENGTH / (end - start).max(f64::EPSILON));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: f64 = 0.001;
+
+    /// Slip for a run whose position at time `t` is `position(t)`, recorded in fixed steps
+    fn run(position: impl Fn(f64) -> f64) -> TimingSlip {
+        let mut slip = TimingSlip::default();
+        let mut time = 0.0;
+        while slip.quarter_mile.is_none() && time < 60.0 {
+            slip.record(position(time), position(time + STEP), time, time + STEP);
+            time += STEP;
+        }
+        slip
+    }
+
+    fn assert_near(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() < 1e-6, "{actual} vs {expected}");
+    }
+
+    #[test]
+    fn splits_and_traps_at_a_steady_speed() {
+        let slip = run(|time| 20.0 * time);
+
+        assert_near(slip.sixty_foot, SIXTY_FOOT / 20.0);
+        assert_near(slip.three_thirty, THREE_THIRTY_FOOT / 20.0);
+        assert_near(slip.eighth_mile, EIGHTH_MILE / 20.0);
+        assert_near(slip.thousand_foot, THOUSAND_FOOT / 20.0);
+        assert_near(slip.quarter_mile, QUARTER_MILE / 20.0);
+        assert_near(slip.eighth_mile_speed, 20.0);
+        assert_near(slip.quarter_mile_speed, 20.0);
+    }
+
+    #[test]
+    fn traps_average_the_speed_over_the_last_66_feet() {
+        // Constant 4 m/s² from a standstill, so x = 2t² and t = sqrt(x / 2)
+        let slip = run(|time| 2.0 * time * time);
+        let time_at = |x: f64| (x / 2.0).sqrt();
+
+        assert_near(slip.sixty_foot, time_at(SIXTY_FOOT));
+        assert_near(slip.quarter_mile, time_at(QUARTER_MILE));
+        let trap = TRAP_LENGTH / (time_at(QUARTER_MILE) - time_at(QUARTER_MILE - TRAP_LENGTH));
+        assert_near(slip.quarter_mile_speed, trap);
+        assert!(slip.quarter_mile_speed.unwrap() < 4.0 * slip.quarter_mile.unwrap());
+    }
+
+    #[test]
+    fn one_step_past_several_markers_times_each_crossing() {
+        let mut slip = TimingSlip::default();
+        slip.record(0.0, EIGHTH_MILE, 0.0, 10.0);
+
+        assert_near(slip.sixty_foot, 10.0 * SIXTY_FOOT / EIGHTH_MILE);
+        assert_near(slip.three_thirty, 10.0 * THREE_THIRTY_FOOT / EIGHTH_MILE);
+        assert_near(slip.eighth_mile, 10.0);
+        assert_near(slip.eighth_mile_speed, EIGHTH_MILE / 10.0);
+        assert_eq!(slip.thousand_foot, None);
+    }
+
+    #[test]
+    fn standing_still_or_rolling_back_records_nothing() {
+        let mut slip = TimingSlip::default();
+        slip.record(0.0, 0.0, 0.0, 1.0);
+        slip.record(30.0, 10.0, 1.0, 2.0);
+        assert_eq!(slip.sixty_foot, None);
+    }
+}
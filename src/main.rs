@@ -9,7 +9,9 @@ use std::time::{Duration, Instant};
 
 mod app;
 mod audio;
+mod catalog;
 mod game;
+mod replay;
 mod ui;
 
 use app::{App, AppState};
@@ -48,7 +50,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         if last_tick.elapsed() >= tick_rate {
-            let delta = last_tick.elapsed().as_secs_f64();
+            // Measure and reset the frame clock together so no real time is lost between frames
+            let now = Instant::now();
+            let delta = now.duration_since(last_tick).as_secs_f64();
+            last_tick = now;
             app.update(delta);
 
             // Update audio
@@ -59,7 +64,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 audio_engine.update_beeps(delta as f32);
 
                 match app.state {
-                    AppState::Racing => {
+                    AppState::Racing | AppState::Replay => {
                         if let Some(race) = &app.race_state {
                             // Play beeps for Christmas tree state changes
                             if race.christmas_tree.state != last_light_state {
@@ -72,28 +77,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     game::LightState::Green => {
                                         audio_engine.play_beep(BeepType::Green);
                                     }
-                                    game::LightState::Racing => {
-                                        // Check for red light
-                                        if let Some(rt) = race.player.reaction_time
-                                            && rt < 0.0
-                                        {
-                                            audio_engine.play_beep(BeepType::RedLight);
-                                        }
+                                    // Check for red light
+                                    game::LightState::Racing if race.player.red_light => {
+                                        audio_engine.play_beep(BeepType::RedLight);
                                     }
                                     _ => {}
                                 }
                                 last_light_state = race.christmas_tree.state;
                             }
 
-                            // Update engine sound based on player RPM (only if player hasn't finished)
-                            if race.player.finish_time.is_none() {
+                            // Update engine sound based on player RPM (only while the player is still running)
+                            if race.player.finish_time.is_none() && !race.player.blown_engine {
                                 audio_engine.update_engine(
                                     race.player.rpm,
                                     race.player.throttle as f32,
                                     app.player_car.redline,
+                                    race.player_on_two_step(),
                                 );
                             } else {
-                                // Player has finished, stop engine sound
+                                // Player has finished or blown up, stop engine sound
                                 audio_engine.stop();
                             }
                         }
@@ -108,8 +110,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 // Always silence when muted
                 audio_engine.stop();
             }
-
-            last_tick = Instant::now();
         }
 
         if app.should_quit {
@@ -134,7 +134,17 @@ fn handle_input(app: &mut App, key: KeyEvent, audio_engine: Option<&audio::Audio
     match app.state {
         AppState::Menu => match (key.code, key.kind) {
             (KeyCode::Char('q') | KeyCode::Esc, KeyEventKind::Press) => return true,
-            (KeyCode::Char('1'), KeyEventKind::Press) => app.start_race(),
+            (KeyCode::Char('1'), KeyEventKind::Press) => app.start_quick_race(),
+            (KeyCode::Char('2'), KeyEventKind::Press) => app.load_latest_replay(),
+            (KeyCode::Char('3'), KeyEventKind::Press) => app.start_ghost_race(),
+            (KeyCode::Char('b'), KeyEventKind::Press) => app.toggle_bracket_mode(),
+            (KeyCode::Char('t'), KeyEventKind::Press) => app.toggle_tree_type(),
+            (KeyCode::Char('p'), KeyEventKind::Press) => app.cycle_throttle_response(),
+            (KeyCode::Char('d'), KeyEventKind::Press) => app.cycle_ai_difficulty(),
+            (KeyCode::Char('+') | KeyCode::Char('='), KeyEventKind::Press) => {
+                app.increase_dial_in()
+            }
+            (KeyCode::Char('-'), KeyEventKind::Press) => app.decrease_dial_in(),
             (KeyCode::Left, KeyEventKind::Press) => app.select_previous_car(),
             (KeyCode::Right, KeyEventKind::Press) => app.select_next_car(),
             (KeyCode::Char('m'), KeyEventKind::Press) => app.toggle_mute(audio_engine),
@@ -145,17 +155,27 @@ fn handle_input(app: &mut App, key: KeyEvent, audio_engine: Option<&audio::Audio
             (KeyCode::Char(' '), KeyEventKind::Release) => app.set_throttle_pressed(false),
             (KeyCode::Up | KeyCode::Char('w'), KeyEventKind::Press) => app.shift_up(),
             (KeyCode::Up | KeyCode::Char('w'), KeyEventKind::Release) => app.reset_shift_state(),
+            (KeyCode::Char(c @ '0'..='9'), KeyEventKind::Press) => {
+                app.set_throttle_step(c.to_digit(10).unwrap_or(0))
+            }
+            (KeyCode::Char('l'), KeyEventKind::Press) => app.set_launch_control_pressed(true),
+            (KeyCode::Char('l'), KeyEventKind::Release) => app.set_launch_control_pressed(false),
+            (KeyCode::Char('c'), KeyEventKind::Press) => app.set_clutch_pressed(true),
+            (KeyCode::Char('c'), KeyEventKind::Release) => app.set_clutch_pressed(false),
             (KeyCode::Char('n'), KeyEventKind::Press) => app.set_nitrous_pressed(true),
             (KeyCode::Char('n'), KeyEventKind::Release) => app.set_nitrous_pressed(false),
             (KeyCode::Char('m'), KeyEventKind::Press) => app.toggle_mute(audio_engine),
-            (KeyCode::Esc, KeyEventKind::Press) => {
-                app.reset_all_key_states();
-                app.state = AppState::Menu;
-            }
+            (KeyCode::Esc, KeyEventKind::Press) => app.stop_race(),
+            _ => {}
+        },
+        AppState::Replay => match (key.code, key.kind) {
+            (KeyCode::Char('m'), KeyEventKind::Press) => app.toggle_mute(audio_engine),
+            (KeyCode::Esc, KeyEventKind::Press) => app.stop_race(),
             _ => {}
         },
         AppState::Results => match (key.code, key.kind) {
             (KeyCode::Char('r'), KeyEventKind::Press) => app.start_race(),
+            (KeyCode::Char('v'), KeyEventKind::Press) => app.watch_last_replay(),
             (KeyCode::Char('q'), KeyEventKind::Press) => return true,
             (KeyCode::Char('m'), KeyEventKind::Press) => app.toggle_mute(audio_engine),
             (KeyCode::Esc, KeyEventKind::Press) => {
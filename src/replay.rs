@@ -0,0 +1,179 @@
+use crate::catalog::config_dir;
+use crate::game::{Car, Ghost, RaceSettings};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Bumped whenever the simulation changes, since an older replay's inputs would run a
+// different race
+const REPLAY_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PlayerInput {
+    ThrottlePressed,
+    ThrottleReleased,
+    ShiftUp,
+    NitrousPressed,
+    NitrousReleased,
+    LaunchControlPressed,
+    LaunchControlReleased,
+    ClutchPressed,
+    ClutchReleased,
+    /// Furthest the pedal may open, chosen with the number keys
+    ThrottleLimit(f64),
+}
+
+/// A player input stamped with the race simulation time it was applied at
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct InputEvent {
+    pub time: f64,
+    pub input: PlayerInput,
+}
+
+/// Everything needed to re-run a race exactly: both cars, the RNG seed, the race settings
+/// and the player's inputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub player_car: Car,
+    pub opponent_car: Car,
+    pub seed: u64,
+    #[serde(default)]
+    pub settings: RaceSettings,
+    pub events: Vec<InputEvent>,
+    /// Ghost the race was run against, if any
+    #[serde(default)]
+    pub ghost: Option<Ghost>,
+}
+
+impl Replay {
+    pub fn new(
+        player_car: Car,
+        opponent_car: Car,
+        seed: u64,
+        settings: RaceSettings,
+        events: Vec<InputEvent>,
+        ghost: Option<Ghost>,
+    ) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            player_car,
+            opponent_car,
+            seed,
+            settings,
+            events,
+            ghost,
+        }
+    }
+
+    /// Write the replay to a new timestamped file in the replays directory
+    pub fn save(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let dir = replays_dir().ok_or("No config directory available")?;
+        fs::create_dir_all(&dir)?;
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let path = dir.join(format!("replay-{}.json", timestamp));
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    /// Load the most recently saved replay
+    pub fn load_latest() -> Result<Self, Box<dyn std::error::Error>> {
+        let dir = replays_dir().ok_or("No config directory available")?;
+        let latest = fs::read_dir(&dir)
+            .map_err(|_| "No replays saved yet")?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .max_by_key(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .ok_or("No replays saved yet")?;
+
+        let replay: Self = serde_json::from_str(&fs::read_to_string(&latest)?)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!("Unsupported replay version {}", replay.version).into());
+        }
+        replay.player_car.validate()?;
+        replay.opponent_car.validate()?;
+        replay.settings.conditions.validate()?;
+        Ok(replay)
+    }
+}
+
+pub fn replays_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("replays"))
+}
+
+pub fn ghosts_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("ghosts"))
+}
+
+/// Personal best ghost for the named car, if one has been recorded
+pub fn load_ghost(car_name: &str) -> Result<Option<Ghost>, Box<dyn std::error::Error>> {
+    let Some(path) = ghost_path(car_name) else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let ghost: Ghost = serde_json::from_str(&fs::read_to_string(&path)?)?;
+    ghost.car.validate()?;
+    Ok(Some(ghost))
+}
+
+pub fn save_ghost(ghost: &Ghost) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = ghost_path(&ghost.car.name).ok_or("No config directory available")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Ghosts hold thousands of snapshots, so skip pretty printing
+    fs::write(&path, serde_json::to_string(ghost)?)?;
+    Ok(path)
+}
+
+// The slug keeps the file recognisable, and a hash of the full name keeps cars whose
+// names only differ in punctuation or case from sharing a ghost
+fn ghost_path(car_name: &str) -> Option<PathBuf> {
+    let slug: String = car_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let hash = name_hash(car_name);
+    ghosts_dir().map(|dir| dir.join(format!("{}-{:08x}.json", slug, hash)))
+}
+
+// 32-bit FNV-1a, which unlike std's hasher is guaranteed to give the same file name
+// in every build
+fn name_hash(name: &str) -> u32 {
+    name.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ghost_files_keep_apart_cars_with_the_same_slug() {
+        let civic = ghost_path("Honda Civic Si").unwrap();
+        assert_eq!(civic, ghost_path("Honda Civic Si").unwrap());
+        assert_ne!(civic, ghost_path("Honda Civic SI").unwrap());
+        assert_ne!(civic, ghost_path("Honda Civic-Si").unwrap());
+
+        let file_name = civic.file_name().unwrap().to_string_lossy();
+        assert!(file_name.starts_with("honda-civic-si-"), "{file_name}");
+    }
+
+    #[test]
+    fn name_hash_is_fnv_1a() {
+        assert_eq!(name_hash(""), 0x811c_9dc5);
+        assert_eq!(name_hash("a"), 0xe40c_292c);
+    }
+}
@@ -7,14 +7,18 @@ use ratatui::{
 };
 
 use crate::app::{App, AppState};
-use crate::game::{LightState, ShiftQuality};
+use crate::game::{
+    AiDifficulty, ChristmasTree, Drivetrain, LaneOutcome, LightState, NosStrategy, Personality,
+    RaceCarState, RaceMode, ShiftQuality, ThrottleResponse, TrackConditions, TrackPrep,
+    Transmission, TreeType,
+};
 
 pub fn draw(f: &mut Frame, app: &App) {
     match app.state {
         AppState::Menu => draw_menu(f, app),
-        AppState::Racing => {
+        AppState::Racing | AppState::Replay => {
             if let Some(race) = &app.race_state {
-                draw_race(f, race);
+                draw_race(f, race, app.state == AppState::Replay);
             }
         }
         AppState::Results => {
@@ -28,7 +32,7 @@ pub fn draw(f: &mut Frame, app: &App) {
 fn draw_menu(f: &mut Frame, app: &App) {
     let area = f.area();
 
-    let text = vec![
+    let mut text = vec![
         Line::from(""),
         Line::from(Span::styled(
             "DRAG-RS",
@@ -41,8 +45,19 @@ fn draw_menu(f: &mut Frame, app: &App) {
         Line::from(""),
         Line::from(format!("Selected Car: {}", app.player_car.name)),
         Line::from(format!(
-            "Horsepower: {} | Weight: {}kg | Redline: {} RPM",
-            app.player_car.horsepower, app.player_car.weight, app.player_car.redline
+            "Horsepower: {} | Weight: {}kg | Redline: {} RPM | {} | {}",
+            app.player_car.horsepower,
+            app.player_car.weight,
+            app.player_car.redline,
+            drivetrain_name(app.player_car.drivetrain),
+            transmission_name(app.player_car.transmission)
+        )),
+        Line::from(format!(
+            "Perfect driver: {} | shifts at {} RPM",
+            app.perfect_run()
+                .and_then(|run| run.et)
+                .map_or("-".to_string(), |et| format!("{:.3}s", et)),
+            rpm_list(app.perfect_run().map_or(&[][..], |run| &run.shift_rpms))
         )),
         Line::from(""),
         Line::from(format!(
@@ -53,13 +68,58 @@ fn draw_menu(f: &mut Frame, app: &App) {
                 Span::styled("ON", Style::default().fg(Color::Green))
             }
         )),
+        Line::from(if app.bracket_mode {
+            format!("Mode: Bracket | Dial-in: {:.2}s", app.player_dial_in)
+        } else {
+            "Mode: Heads-Up".to_string()
+        }),
+        Line::from(format!(
+            "Tree: {} (perfect R/T {:.3})",
+            tree_name(app.tree_type),
+            app.tree_type.perfect_reaction()
+        )),
+        Line::from(format!(
+            "Throttle: {}",
+            throttle_response_name(app.throttle_response)
+        )),
+        Line::from(format!(
+            "Opponent: {}",
+            ai_difficulty_name(app.ai_difficulty)
+        )),
+        Line::from(format!("Conditions: {}", conditions_text(&app.conditions))),
         Line::from(""),
         Line::from("[←/→] Select Car"),
         Line::from("[1] Quick Race"),
+        Line::from("[2] Watch Last Replay"),
+        Line::from("[3] Race Your Best (Ghost)"),
+        Line::from("[B] Toggle Bracket Mode | [+/-] Dial-in"),
+        Line::from("[T] Toggle Pro/Sportsman Tree"),
+        Line::from("[P] Cycle Throttle Response"),
+        Line::from("[D] Cycle Opponent Difficulty"),
         Line::from("[M] Toggle Audio"),
         Line::from("[Q] Quit"),
     ];
 
+    if let Some(message) = &app.status_message {
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            message.as_str(),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    if let Some(error) = app.car_load_errors.first() {
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            format!(
+                "{} car file(s) rejected - {}",
+                app.car_load_errors.len(),
+                error
+            ),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
     let paragraph = Paragraph::new(text)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::ALL).title("Main Menu"));
@@ -67,7 +127,7 @@ fn draw_menu(f: &mut Frame, app: &App) {
     f.render_widget(paragraph, area);
 }
 
-fn draw_race(f: &mut Frame, race: &crate::game::RaceState) {
+fn draw_race(f: &mut Frame, race: &crate::game::RaceState, replaying: bool) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -79,10 +139,26 @@ fn draw_race(f: &mut Frame, race: &crate::game::RaceState) {
         ])
         .split(f.area());
 
-    // Header
-    let header = Paragraph::new(format!("DRAG-RS | ET: {:.3}s", race.elapsed_time))
-        .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
+    // Header, showing the day's conditions until the launch
+    let status = if race.race_started {
+        format!("ET: {:.3}s", race.player.run_time)
+    } else {
+        conditions_text(&race.settings.conditions)
+    };
+    let header = Paragraph::new(format!(
+        "DRAG-RS{}{} | {}",
+        if replaying { " | ▶ REPLAY" } else { "" },
+        match race.settings.mode {
+            RaceMode::HeadsUp => String::new(),
+            RaceMode::Bracket {
+                player_dial,
+                opponent_dial,
+            } => format!(" | Dial {:.2} vs {:.2}", player_dial, opponent_dial),
+        },
+        status
+    ))
+    .alignment(Alignment::Center)
+    .block(Block::default().borders(Borders::ALL));
     f.render_widget(header, chunks[0]);
 
     // Christmas tree and distance
@@ -95,15 +171,71 @@ fn draw_race(f: &mut Frame, race: &crate::game::RaceState) {
     draw_gauges(f, chunks[3], race);
 
     // Controls
-    let controls =
-        Paragraph::new("[SPACE] Throttle | [↑/W] Shift | [N] Nitrous | [M] Mute | [ESC] Menu")
-            .alignment(Alignment::Center)
-            .block(Block::default().borders(Borders::ALL));
+    let controls = Paragraph::new(if replaying {
+        "[M] Mute | [ESC] Stop Replay"
+    } else {
+        "[SPACE] Throttle | [1-0] Limit | [L] Launch Control | [C] Clutch | [↑/W] Shift | [N] Nitrous | [M] Mute | [ESC] Menu"
+    })
+    .alignment(Alignment::Center)
+    .block(Block::default().borders(Borders::ALL));
     f.render_widget(controls, chunks[4]);
 }
 
 fn draw_christmas_tree(f: &mut Frame, area: Rect, race: &crate::game::RaceState) {
-    let tree_text = match race.christmas_tree.state {
+    // Each lane has its own tree, player on the left like the track below
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let player_block = Block::default().borders(Borders::ALL).title("You");
+    let player_tree = Paragraph::new(tree_lines(&race.christmas_tree, &race.player, "You"))
+        .alignment(Alignment::Left)
+        .block(player_block);
+    f.render_widget(player_tree, chunks[0]);
+
+    // The AI opponent's name and style, so you know what you're up against
+    let personality = race.ai.personality();
+    let (opponent_name, opponent_block) = if race.ghost.is_some() {
+        (
+            "Ghost",
+            Block::default().borders(Borders::ALL).title("Ghost"),
+        )
+    } else if race.ai.is_perfect() {
+        // The perfect driver shifts by the physics, not by its personality
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Perfect Driver")
+            .title_bottom(format!(
+                "{} nitrous | shifts at {} RPM",
+                nos_strategy_name(personality.nos_strategy()),
+                rpm_list(race.ai.optimal_shift_rpms())
+            ));
+        ("Perfect", block)
+    } else {
+        let name = personality.driver_name();
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("{} ({})", name, personality_name(personality)))
+            .title_bottom(format!(
+                "{} nitrous | shifts at {:.0}%",
+                nos_strategy_name(personality.nos_strategy()),
+                personality.shift_point() * 100.0
+            ));
+        (name, block)
+    };
+    let opponent_tree = Paragraph::new(tree_lines(
+        &race.opponent_tree,
+        &race.opponent,
+        opponent_name,
+    ))
+    .alignment(Alignment::Left)
+    .block(opponent_block);
+    f.render_widget(opponent_tree, chunks[1]);
+}
+
+fn tree_lines<'a>(tree: &ChristmasTree, lane: &RaceCarState, who: &str) -> Vec<Line<'a>> {
+    match tree.state {
         LightState::PreStage => vec![
             Line::from("  ⚪ Pre-Stage"),
             Line::from("  ⚫ Staged"),
@@ -157,57 +289,31 @@ fn draw_christmas_tree(f: &mut Frame, area: Rect, race: &crate::game::RaceState)
             Line::from("  ⚫"),
             Line::from(""),
         ],
-        LightState::Racing => {
-            if let Some(rt) = race.player.reaction_time {
-                if rt < 0.0 {
-                    vec![
-                        Line::from(""),
-                        Line::from(Span::styled(
-                            "  🔴 RED LIGHT!",
-                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                        )),
-                        Line::from(""),
-                        Line::from("  You jumped the start!"),
-                        Line::from(""),
-                        Line::from(""),
-                    ]
-                } else {
-                    vec![
-                        Line::from(""),
-                        Line::from(Span::styled(
-                            "  RACING!",
-                            Style::default()
-                                .fg(Color::Green)
-                                .add_modifier(Modifier::BOLD),
-                        )),
-                        Line::from(""),
-                        Line::from(format!("  Distance: {:.1}m / 402.3m", race.player.position)),
-                        Line::from(""),
-                        Line::from(""),
-                    ]
-                }
-            } else {
-                vec![
-                    Line::from(""),
-                    Line::from(Span::styled(
-                        "  RACING!",
-                        Style::default()
-                            .fg(Color::Green)
-                            .add_modifier(Modifier::BOLD),
-                    )),
-                    Line::from(""),
-                    Line::from(format!("  Distance: {:.1}m / 402.3m", race.player.position)),
-                    Line::from(""),
-                    Line::from(""),
-                ]
-            }
-        }
-    };
-
-    let tree = Paragraph::new(tree_text)
-        .alignment(Alignment::Left)
-        .block(Block::default().borders(Borders::ALL).title("Start"));
-    f.render_widget(tree, area);
+        LightState::Racing if lane.red_light => vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                "  🔴 RED LIGHT!",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(format!("  {} jumped the start!", who)),
+            Line::from(""),
+            Line::from(""),
+        ],
+        LightState::Racing => vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                "  RACING!",
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(format!("  Distance: {:.1}m / 402.3m", lane.position)),
+            Line::from(""),
+            Line::from(""),
+        ],
+    }
 }
 
 fn draw_track(f: &mut Frame, area: Rect, race: &crate::game::RaceState) {
@@ -220,14 +326,25 @@ fn draw_track(f: &mut Frame, area: Rect, race: &crate::game::RaceState) {
     let player_pos = (player_progress * (track_width - 1) as f64) as usize;
     let opponent_pos = (opponent_progress * (track_width - 1) as f64) as usize;
 
+    // A ghost gets its own marker and color so it can't be mistaken for an AI car
+    let (opponent_label, opponent_marker, opponent_color) = if race.ghost.is_some() {
+        ("Ghost", '◇', Color::Magenta)
+    } else {
+        ("Opponent", '▶', Color::Red)
+    };
+
     // Build track lines
-    let mut opponent_line = String::from("Opp: ");
+    let mut opponent_line = String::from(if race.ghost.is_some() {
+        "Gst: "
+    } else {
+        "Opp: "
+    });
     let mut player_line = String::from("You: ");
 
     for i in 0..track_width {
         // Opponent line
         if i == opponent_pos {
-            opponent_line.push('▶');
+            opponent_line.push(opponent_marker);
         } else if i == track_width - 1 {
             opponent_line.push('║'); // Finish line
         } else {
@@ -245,15 +362,16 @@ fn draw_track(f: &mut Frame, area: Rect, race: &crate::game::RaceState) {
     }
 
     // Create colored track lines with only the car symbols colored
-    let opponent_spans = create_colored_track_line(&opponent_line, Color::Red);
-    let player_spans = create_colored_track_line(&player_line, Color::Green);
+    let opponent_spans = create_colored_track_line(&opponent_line, opponent_marker, opponent_color);
+    let player_spans = create_colored_track_line(&player_line, '▶', Color::Green);
 
     let track_text = vec![
         Line::from(""),
         Line::from(format!(
-            "Opponent: {:.1} m/s | ET: {:.3}s{}",
+            "{}: {:.1} m/s | ET: {:.3}s{}",
+            opponent_label,
             race.opponent.velocity,
-            race.opponent.reaction_time.unwrap_or(0.0) + race.elapsed_time,
+            race.opponent.run_time,
             if race.opponent.finish_time.is_some() {
                 " ✅"
             } else {
@@ -265,7 +383,7 @@ fn draw_track(f: &mut Frame, area: Rect, race: &crate::game::RaceState) {
         Line::from(format!(
             "Player: {:.1} m/s | ET: {:.3}s{}",
             race.player.velocity,
-            race.player.reaction_time.unwrap_or(0.0) + race.elapsed_time,
+            race.player.run_time,
             if race.player.finish_time.is_some() {
                 " ✅"
             } else {
@@ -302,19 +420,22 @@ fn draw_track(f: &mut Frame, area: Rect, race: &crate::game::RaceState) {
     f.render_widget(track, area);
 }
 
-fn create_colored_track_line(line: &str, car_color: Color) -> Vec<Span<'_>> {
+fn create_colored_track_line(line: &str, marker: char, car_color: Color) -> Vec<Span<'_>> {
     let mut spans = Vec::new();
     let mut current_span = String::new();
 
     for ch in line.chars() {
-        if ch == '▶' {
+        if ch == marker {
             // Add the current span if it's not empty
             if !current_span.is_empty() {
                 spans.push(Span::raw(current_span));
                 current_span = String::new();
             }
             // Add the colored car symbol
-            spans.push(Span::styled("▶", Style::default().fg(car_color)));
+            spans.push(Span::styled(
+                marker.to_string(),
+                Style::default().fg(car_color),
+            ));
         } else {
             current_span.push(ch);
         }
@@ -351,10 +472,21 @@ fn draw_gauges(f: &mut Frame, area: Rect, race: &crate::game::RaceState) {
 
     let rpm_gauge = Gauge::default()
         .block(Block::default().borders(Borders::ALL).title(format!(
-            "RPM: {} / {} | Gear: {}",
+            "RPM: {} / {} | Gear: {}{}",
             race.player.rpm,
             race.player_car.redline,
-            race.player.gear + 1
+            race.player.gear + 1,
+            if race.player_on_two_step() {
+                " | 🔥 TWO-STEP"
+            } else if race.player.clutch_in {
+                " | CLUTCH IN"
+            } else if race.player.shift_timer > 0.0 {
+                " | SHIFTING"
+            } else if race.player.is_wheelspinning() {
+                " | 💨 WHEELSPIN"
+            } else {
+                ""
+            }
         )))
         .gauge_style(Style::default().fg(rpm_color))
         .percent(rpm_percent.min(100));
@@ -407,9 +539,10 @@ fn draw_gauges(f: &mut Frame, area: Rect, race: &crate::game::RaceState) {
             race.player.velocity, race.player.top_speed
         )),
         Line::from(format!(
-            "Perfect Shifts: {} | Throttle: {:.0}%",
+            "Perfect Shifts: {} | Throttle: {:.0}% (limit {:.0}%)",
             race.player.perfect_shifts,
-            race.player.throttle * 100.0
+            race.player.throttle * 100.0,
+            race.player_throttle_limit() * 100.0
         )),
     ];
     let stats =
@@ -420,29 +553,33 @@ fn draw_gauges(f: &mut Frame, area: Rect, race: &crate::game::RaceState) {
 fn draw_results(f: &mut Frame, race: &crate::game::RaceState, app: &App) {
     let area = f.area();
 
-    let (winner_text, winner_color) = if let Some(rt) = race.player.reaction_time {
-        if rt < 0.0 {
-            ("RED LIGHT! 🔴", Color::Red)
-        } else {
-            match race.winner {
-                Some(crate::game::Winner::Player) => ("YOU WIN! 🏆", Color::Green),
-                Some(crate::game::Winner::Opponent) => ("YOU LOSE", Color::Red),
-                None => ("DRAW", Color::Yellow),
-            }
-        }
-    } else {
-        match race.winner {
-            Some(crate::game::Winner::Player) => ("YOU WIN! 🏆", Color::Green),
-            Some(crate::game::Winner::Opponent) => ("YOU LOSE", Color::Red),
-            None => ("DRAW", Color::Yellow),
-        }
+    let (player_outcome, opponent_outcome) = race.outcomes();
+    let (winner_text, winner_color) = match race.winner {
+        Some(crate::game::Winner::Player) => (
+            match opponent_outcome {
+                Some(LaneOutcome::RedLight) => "YOU WIN! Opponent red-lit 🏆",
+                Some(LaneOutcome::DnfBlown) => "YOU WIN! Opponent blew up 💥",
+                Some(LaneOutcome::Breakout) => "YOU WIN! Opponent broke out 🏆",
+                _ => "YOU WIN! 🏆",
+            },
+            Color::Green,
+        ),
+        Some(crate::game::Winner::Opponent) => (
+            match player_outcome {
+                Some(LaneOutcome::RedLight) => "RED LIGHT! 🔴",
+                Some(LaneOutcome::DnfBlown) => "BLOWN ENGINE! DNF 💥",
+                Some(LaneOutcome::Breakout) => "BREAKOUT! You ran under your dial",
+                _ => "YOU LOSE",
+            },
+            Color::Red,
+        ),
+        None => ("DRAW", Color::Yellow),
     };
 
-    let player_et = race.player.finish_time.unwrap_or(999.0);
-    let opponent_et = race.opponent.finish_time.unwrap_or(999.0);
-    let player_rt = race.player.reaction_time.unwrap_or(0.0);
+    let player_slip = &race.player.slip;
+    let opponent_slip = &race.opponent.slip;
 
-    let results_text = vec![
+    let mut results_text = vec![
         Line::from(""),
         Line::from(Span::styled(
             winner_text,
@@ -452,17 +589,127 @@ fn draw_results(f: &mut Frame, race: &crate::game::RaceState, app: &App) {
         )),
         Line::from(""),
         Line::from("═══════════════════════════════"),
-        Line::from(""),
-        Line::from(format!("Your Time:      {:.3}s", player_et)),
-        Line::from(format!("Opponent Time:  {:.3}s", opponent_et)),
+        Line::from(Span::styled(
+            format!(
+                "{:<10}{:>10}{:>11}",
+                "",
+                "YOU",
+                if race.ghost.is_some() {
+                    "GHOST"
+                } else {
+                    "OPPONENT"
+                }
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
         Line::from(format!(
-            "Margin:         {:.3}s",
-            (player_et - opponent_et).abs()
+            "{:<10}{:>10}{:>11}",
+            "RESULT",
+            outcome_name(player_outcome),
+            outcome_name(opponent_outcome)
         )),
-        Line::from(""),
-        Line::from(format!("Reaction Time:  {:.3}s", player_rt)),
+        slip_row(
+            "R/T",
+            race.player.reaction_time,
+            race.opponent.reaction_time,
+            3,
+        ),
+        slip_row("60'", player_slip.sixty_foot, opponent_slip.sixty_foot, 3),
+        slip_row(
+            "330'",
+            player_slip.three_thirty,
+            opponent_slip.three_thirty,
+            3,
+        ),
+        slip_row(
+            "1/8 ET",
+            player_slip.eighth_mile,
+            opponent_slip.eighth_mile,
+            3,
+        ),
+        slip_row(
+            "1/8 MPH",
+            player_slip.eighth_mile_speed.map(to_mph),
+            opponent_slip.eighth_mile_speed.map(to_mph),
+            2,
+        ),
+        slip_row(
+            "1000'",
+            player_slip.thousand_foot,
+            opponent_slip.thousand_foot,
+            3,
+        ),
+        slip_row(
+            "1/4 ET",
+            player_slip.quarter_mile,
+            opponent_slip.quarter_mile,
+            3,
+        ),
+        slip_row(
+            "1/4 MPH",
+            player_slip.quarter_mile_speed.map(to_mph),
+            opponent_slip.quarter_mile_speed.map(to_mph),
+            2,
+        ),
+    ];
+
+    // Show how early each red light left, since both lanes may have fouled
+    let perfect = race.settings.tree.perfect_reaction();
+    for (who, lane) in [("You", &race.player), ("Opponent", &race.opponent)] {
+        if lane.red_light
+            && let Some(rt) = lane.reaction_time
+        {
+            results_text.push(Line::from(Span::styled(
+                format!("{} red-lit by {:.3}s", who, perfect - rt),
+                Style::default().fg(Color::Red),
+            )));
+        }
+    }
+
+    for (who, lane) in [("You", &race.player), ("Opponent", &race.opponent)] {
+        if lane.blown_engine {
+            results_text.push(Line::from(Span::styled(
+                format!("{} blew the engine at {:.0}m", who, lane.position),
+                Style::default().fg(Color::Red),
+            )));
+        }
+    }
+
+    if let RaceMode::Bracket {
+        player_dial,
+        opponent_dial,
+    } = race.settings.mode
+    {
+        results_text.push(slip_row("DIAL", Some(player_dial), Some(opponent_dial), 2));
+
+        let (player_breakout, opponent_breakout) = race.breakouts();
+        for (who, breakout) in [("You", player_breakout), ("Opponent", opponent_breakout)] {
+            if let Some(amount) = breakout {
+                results_text.push(Line::from(Span::styled(
+                    format!("{} broke out by {:.3}s", who, amount),
+                    Style::default().fg(Color::Red),
+                )));
+            }
+        }
+    }
+
+    results_text.extend([
+        Line::from("═══════════════════════════════"),
+        Line::from(match race.finish_margin() {
+            Some(margin) => format!("Margin:         {:.3}s", margin),
+            None => "Margin:         -".to_string(),
+        }),
         Line::from(format!("Top Speed:      {:.1} m/s", race.player.top_speed)),
         Line::from(format!("Perfect Shifts: {}", race.player.perfect_shifts)),
+        Line::from(
+            match (app.race_benchmark_et(race), race.player.finish_time) {
+                (Some(benchmark), Some(et)) => {
+                    format!("Benchmark ET:   {:.3}s ({:+.3})", benchmark, et - benchmark)
+                }
+                (Some(benchmark), None) => format!("Benchmark ET:   {:.3}s", benchmark),
+                (None, _) => "Benchmark ET:   -".to_string(),
+            },
+        ),
         Line::from(""),
         Line::from(format!(
             "Audio: {}",
@@ -476,9 +723,18 @@ fn draw_results(f: &mut Frame, race: &crate::game::RaceState, app: &App) {
         Line::from("═══════════════════════════════"),
         Line::from(""),
         Line::from("[R] Race Again"),
+        Line::from("[V] Watch Replay"),
         Line::from("[M] Toggle Audio"),
         Line::from("[Q] Quit to Menu"),
-    ];
+    ]);
+
+    if let Some(message) = &app.status_message {
+        results_text.push(Line::from(""));
+        results_text.push(Line::from(Span::styled(
+            message.as_str(),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
 
     let paragraph = Paragraph::new(results_text)
         .alignment(Alignment::Center)
@@ -486,3 +742,121 @@ fn draw_results(f: &mut Frame, race: &crate::game::RaceState, app: &App) {
 
     f.render_widget(paragraph, area);
 }
+
+fn slip_row(label: &str, player: Option<f64>, opponent: Option<f64>, precision: usize) -> Line<'_> {
+    let format_value = |value: Option<f64>| match value {
+        Some(v) => format!("{:.*}", precision, v),
+        None => "-".to_string(),
+    };
+
+    Line::from(format!(
+        "{:<10}{:>10}{:>11}",
+        label,
+        format_value(player),
+        format_value(opponent)
+    ))
+}
+
+fn outcome_name(outcome: Option<LaneOutcome>) -> &'static str {
+    match outcome {
+        Some(LaneOutcome::Finished) => "Finished",
+        Some(LaneOutcome::DnfBlown) => "DNF-Blown",
+        Some(LaneOutcome::RedLight) => "Red light",
+        Some(LaneOutcome::Breakout) => "Breakout",
+        None => "-",
+    }
+}
+
+fn to_mph(meters_per_second: f64) -> f64 {
+    meters_per_second * 2.236_936
+}
+
+fn drivetrain_name(drivetrain: Drivetrain) -> &'static str {
+    match drivetrain {
+        Drivetrain::Fwd => "FWD",
+        Drivetrain::Rwd => "RWD",
+        Drivetrain::Awd => "AWD",
+    }
+}
+
+fn transmission_name(transmission: Transmission) -> &'static str {
+    match transmission {
+        Transmission::Manual => "Manual",
+        Transmission::Automatic => "Automatic",
+        Transmission::Sequential => "Sequential",
+        Transmission::Dct => "DCT",
+    }
+}
+
+fn throttle_response_name(response: ThrottleResponse) -> &'static str {
+    match response {
+        ThrottleResponse::Instant => "Instant",
+        ThrottleResponse::Quick => "Quick",
+        ThrottleResponse::Progressive => "Progressive",
+    }
+}
+
+fn conditions_text(conditions: &TrackConditions) -> String {
+    format!(
+        "{:.0}°C, {:.0}% RH, {:.0}m (DA {:.0}m) | Track {:.0}°C, {}",
+        conditions.temperature,
+        conditions.humidity * 100.0,
+        conditions.altitude,
+        conditions.density_altitude(),
+        conditions.track_temp,
+        track_prep_name(conditions.prep)
+    )
+}
+
+fn track_prep_name(prep: TrackPrep) -> &'static str {
+    match prep {
+        TrackPrep::Street => "Street",
+        TrackPrep::Prepped => "Prepped",
+        TrackPrep::Glued => "Glued",
+    }
+}
+
+fn personality_name(personality: Personality) -> &'static str {
+    match personality {
+        Personality::Cautious => "Cautious",
+        Personality::Aggressive => "Aggressive",
+        Personality::Consistent => "Consistent",
+        Personality::Showboat => "Showboat",
+        Personality::Tactician => "Tactician",
+    }
+}
+
+fn nos_strategy_name(strategy: NosStrategy) -> &'static str {
+    match strategy {
+        NosStrategy::Launch => "Launch",
+        NosStrategy::Midrange => "Midrange",
+        NosStrategy::Late => "Late",
+        NosStrategy::Burst => "Burst",
+        NosStrategy::Adaptive => "Adaptive",
+    }
+}
+
+// Shift points in gear order, "6250 / 6400 / ..."
+fn rpm_list(rpms: &[u32]) -> String {
+    rpms.iter()
+        .map(|rpm| rpm.to_string())
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+fn ai_difficulty_name(difficulty: AiDifficulty) -> &'static str {
+    match difficulty {
+        AiDifficulty::Easy => "Easy",
+        AiDifficulty::Medium => "Medium",
+        AiDifficulty::Hard => "Hard",
+        AiDifficulty::Pro => "Pro",
+        AiDifficulty::Perfect => "Perfect",
+    }
+}
+
+fn tree_name(tree_type: TreeType) -> &'static str {
+    match tree_type {
+        TreeType::Sportsman => "Sportsman",
+        TreeType::Pro => "Pro",
+    }
+}